@@ -0,0 +1,98 @@
+use crate::driver::{ParsedDocument, SyntaxNode};
+use crate::extensions::table_of_contents::TocEntry;
+use magnus::{Error, RArray, RHash};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// `MarkdownIt::Document`, returned by `MarkdownIt.parse`
+#[magnus::wrap(class = "MarkdownIt::Document", free_immediately, size)]
+pub(crate) struct Document {
+    root: Arc<SyntaxNode>,
+    toc: Vec<TocEntry>,
+    warnings: Vec<String>,
+}
+
+// `MarkdownIt::Node`, each node of the document (including the root node itself)
+#[magnus::wrap(class = "MarkdownIt::Node", free_immediately, size)]
+pub(crate) struct DocumentNode {
+    node: Arc<SyntaxNode>,
+}
+
+impl Document {
+    pub(crate) fn new(parsed: ParsedDocument) -> Self {
+        Self {
+            root: Arc::new(parsed.tree),
+            toc: parsed.toc,
+            warnings: parsed.warnings,
+        }
+    }
+
+    pub(crate) fn root(&self) -> DocumentNode {
+        DocumentNode {
+            node: self.root.clone(),
+        }
+    }
+
+    pub(crate) fn children(&self) -> RArray {
+        self.root().children()
+    }
+
+    // nested headings, e.g. [{ "level" => 2, "id" => "...", "text" => "...", "children" => [...] }]
+    pub(crate) fn toc(&self) -> Result<RArray, Error> {
+        Self::toc_entries(&self.toc)
+    }
+
+    // e.g. `["line 3: image without alt text (/a.jpg)"]`
+    pub(crate) fn warnings(&self) -> Vec<String> {
+        self.warnings.clone()
+    }
+
+    fn toc_entries(entries: &[TocEntry]) -> Result<RArray, Error> {
+        let array = RArray::with_capacity(entries.len());
+        for entry in entries {
+            let hash = RHash::new();
+            hash.aset("level", entry.level)?;
+            hash.aset("id", entry.id.as_str())?;
+            hash.aset("text", entry.text.as_str())?;
+            hash.aset("children", Self::toc_entries(&entry.children)?)?;
+            array.push(hash)?;
+        }
+        Ok(array)
+    }
+}
+
+impl DocumentNode {
+    pub(crate) fn node_type(&self) -> String {
+        self.node.node_type.to_string()
+    }
+
+    pub(crate) fn attributes(&self) -> HashMap<String, String> {
+        self.node.attributes.iter().cloned().collect()
+    }
+
+    pub(crate) fn children(&self) -> RArray {
+        self.node
+            .children
+            .iter()
+            .map(|child| DocumentNode {
+                node: child.clone(),
+            })
+            .collect()
+    }
+
+    // [start_line, start_column, end_line, end_column]
+    pub(crate) fn source_position(&self) -> Option<Vec<u32>> {
+        self.node.source_position.map(|position| {
+            vec![
+                position.start_line,
+                position.start_column,
+                position.end_line,
+                position.end_column,
+            ]
+        })
+    }
+
+    pub(crate) fn text(&self) -> String {
+        self.node.text.clone()
+    }
+}
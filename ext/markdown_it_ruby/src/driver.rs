@@ -1,56 +1,458 @@
+mod mdast;
 mod options;
+mod syntax_tree;
 
 use crate::extensions;
-use markdown_it::plugins::{cmark, extra, html};
+use crate::extensions::diagnostics::Diagnostics;
+use crate::extensions::link_with_target::LinkWithTarget;
+use crate::extensions::table_of_contents::{TableOfContents, TocEntry};
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::plugins::cmark::{block, inline};
+use markdown_it::plugins::{extra, html};
 use markdown_it::{MarkdownIt, Node};
-pub use options::{InternalDomain, MarkdonwItOptions};
+pub use options::{InternalDomain, MarkdonwItOptions, OptionError, OptionValue};
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::fmt;
+pub use syntax_tree::SyntaxNode;
 
 pub(super) struct MarkdownDriver {
     md: MarkdownIt,
-    source: OnceLock<String>,
-    contents: OnceLock<Node>,
+}
+
+// a parsed document, copied out of markdown_it::Node (which is not Send)
+pub(super) struct ParsedDocument {
+    pub(super) tree: SyntaxNode,
+    // empty unless `table_of_contents` is enabled
+    pub(super) toc: Vec<TocEntry>,
+    // e.g. images without alt text with `missing_alt_text: "warning"`
+    pub(super) warnings: Vec<String>,
+}
+
+// urls of a document which `file_sizes` and `image_dimensions` callbacks are called with
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct LookupUrls {
+    // links annotated as files (with `file_link_annotation`)
+    pub(super) file_sizes: Vec<String>,
+    pub(super) image_dimensions: Vec<String>,
+}
+
+// errors found in the document (e.g. headings out of range with `heading_overflow: "error"`)
+#[derive(Debug, PartialEq)]
+pub(super) struct ConversionError {
+    pub(super) messages: Vec<String>,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.messages.join("\n"))
+    }
+}
+
+impl ConversionError {
+    // outputs of multiple documents in order, or errors of all the failed documents
+    // (e.g. `document at index 2: line 3: ...`)
+    pub(super) fn collect<T>(
+        results: Vec<Result<T, ConversionError>>,
+    ) -> Result<Vec<T>, ConversionError> {
+        let mut outputs = Vec::with_capacity(results.len());
+        let mut messages = Vec::new();
+        for (index, result) in results.into_iter().enumerate() {
+            match result {
+                Ok(output) => outputs.push(output),
+                Err(error) => messages.extend(
+                    error
+                        .messages
+                        .into_iter()
+                        .map(|message| format!("document at index {}: {}", index, message)),
+                ),
+            }
+        }
+
+        if messages.is_empty() {
+            Ok(outputs)
+        } else {
+            Err(ConversionError { messages })
+        }
+    }
+}
+
+pub(super) enum OutputFormat {
+    Html,
+    // JSON following the mdast schema (https://github.com/syntax-tree/mdast)
+    Mdast,
 }
 
 impl MarkdownDriver {
-    pub(super) fn new(env: HashMap<String, String>) -> Self {
+    pub(super) fn new(env: HashMap<String, OptionValue>) -> Result<Self, OptionError> {
         // create markdown parser
         let mut md = MarkdownIt::new();
-        let option = MarkdonwItOptions::new(env.clone());
+        let option = MarkdonwItOptions::new(env)?;
         Self::prepare(&mut md, option);
 
-        Self {
-            md,
-            source: OnceLock::new(),
-            contents: OnceLock::new(),
+        Ok(Self { md })
+    }
+
+    pub(super) fn parse(&self, contents: &str) -> Result<Node, ConversionError> {
+        let root = self.md.parse(contents);
+        match root.ext.get::<Diagnostics>() {
+            Some(diagnostics) if !diagnostics.errors.is_empty() => Err(ConversionError {
+                messages: diagnostics.errors.clone(),
+            }),
+            _ => Ok(root),
         }
     }
 
-    pub(super) fn parse(&self, contents: String) {
-        if self.source.set(contents.clone()).is_err() {
-            return;
+    pub(super) fn render(&self, contents: &Node, format: OutputFormat) -> String {
+        match format {
+            OutputFormat::Html => contents.render(),
+            OutputFormat::Mdast => mdast::render(contents),
         }
+    }
 
-        let root = self.md.parse(contents.as_str());
-        self.contents.set(root).unwrap();
+    pub(super) fn convert(
+        &self,
+        contents: &str,
+        format: OutputFormat,
+    ) -> Result<String, ConversionError> {
+        self.convert_with_warnings(contents, format)
+            .map(|(output, _)| output)
     }
 
-    pub(super) fn render(&self) -> String {
-        let contents = self.contents.get();
-        match contents {
-            None => String::new(),
-            Some(contents) => contents.render(),
+    pub(super) fn convert_with_warnings(
+        &self,
+        contents: &str,
+        format: OutputFormat,
+    ) -> Result<(String, Vec<String>), ConversionError> {
+        let root = self.parse(contents)?;
+        Ok((self.render(&root, format), Self::warnings(&root)))
+    }
+
+    fn warnings(root: &Node) -> Vec<String> {
+        match root.ext.get::<Diagnostics>() {
+            Some(diagnostics) => diagnostics.warnings.clone(),
+            None => Vec::new(),
         }
     }
 
+    pub(super) fn parse_document(&self, contents: &str) -> Result<ParsedDocument, ConversionError> {
+        let root = self.parse(contents)?;
+        let toc = match root.ext.get::<TableOfContents>() {
+            Some(toc) => toc.entries.clone(),
+            None => Vec::new(),
+        };
+
+        Ok(ParsedDocument {
+            tree: SyntaxNode::new(&root),
+            toc,
+            warnings: Self::warnings(&root),
+        })
+    }
+
+    pub(super) fn lookup_urls(&self, contents: &str) -> Result<LookupUrls, ConversionError> {
+        let root = self.parse(contents)?;
+        let mut urls = LookupUrls::default();
+        root.walk(|node, _| {
+            if let Some(link) = node.cast::<LinkWithTarget>() {
+                if link.file.is_some() {
+                    urls.file_sizes.push(link.url.clone());
+                }
+            } else if let Some(image) = node.cast::<Image>() {
+                urls.image_dimensions.push(image.url.clone());
+            }
+        });
+
+        Ok(urls)
+    }
+
     fn prepare(md: &mut MarkdownIt, option: MarkdonwItOptions) {
-        html::add(md);
-        cmark::add(md);
-        extra::add(md);
+        // raw html is rendered as text unless it is allowed (or sanitized later on)
+        if option.is_enabled("html", true)
+            && option.get_option_or_default("html", "allow") != "escape"
+        {
+            html::add(md);
+        }
+        Self::add_syntax(md, &option);
 
         // add custom three rules described above
         extensions::add(md, &option);
         option.add(md);
     }
+
+    // the same as `cmark::add` and `extra::add`, except that syntaxes can be turned off
+    // (e.g. `tables: false`, `images: false`, or a set of them by `preset`)
+    fn add_syntax(md: &mut MarkdownIt, option: &MarkdonwItOptions) {
+        let enabled = |key: &str| option.is_enabled(key, true);
+
+        inline::newline::add(md);
+        inline::escape::add(md);
+        inline::backticks::add(md);
+        inline::emphasis::add(md);
+        inline::link::add(md);
+        if enabled("images") {
+            inline::image::add(md);
+        }
+        inline::autolink::add(md);
+        inline::entity::add(md);
+
+        if enabled("code_blocks") {
+            block::code::add(md);
+            block::fence::add(md);
+        }
+        if enabled("blockquotes") {
+            block::blockquote::add(md);
+        }
+        block::hr::add(md);
+        block::list::add(md);
+        block::reference::add(md);
+        if enabled("headings") {
+            block::heading::add(md);
+            block::lheading::add(md);
+        }
+        block::paragraph::add(md);
+
+        if enabled("strikethrough") {
+            extra::strikethrough::add(md);
+        }
+        // e.g. `<https://example.com/>` is displayed as `example.com`
+        if enabled("beautify_links") {
+            extra::beautify_links::add(md);
+        }
+        if enabled("linkify") {
+            extra::linkify::add(md);
+        }
+        if enabled("tables") {
+            extra::tables::add(md);
+        }
+        if enabled("code_blocks") && enabled("syntax_highlighting") {
+            extra::syntect::add(md);
+        }
+        // smart quotes are a part of the typographer (e.g. `"text"` => `“text”`)
+        if enabled("typographer") {
+            extra::typographer::add(md);
+            extra::smartquotes::add(md);
+        }
+    }
+}
+
+#[test]
+fn test_driver_reuse() {
+    let driver = MarkdownDriver::new(HashMap::from([(
+        "heading_level_offset".to_string(),
+        OptionValue::from(1),
+    )]))
+    .unwrap();
+
+    // the same driver can convert multiple documents
+    assert_eq!(
+        driver.convert("# title 1", OutputFormat::Html).unwrap(),
+        "<h2>title 1</h2>\n"
+    );
+    assert_eq!(
+        driver.convert("# title 2", OutputFormat::Html).unwrap(),
+        "<h2>title 2</h2>\n"
+    );
+    assert_eq!(driver.convert("", OutputFormat::Html).unwrap(), "");
+    assert_eq!(
+        driver.convert("", OutputFormat::Mdast).unwrap(),
+        r#"{"children":[],"position":{"end":{"column":1,"line":1},"start":{"column":1,"line":1}},"type":"root"}"#
+    );
+}
+
+#[test]
+fn test_driver_errors() {
+    let driver = MarkdownDriver::new(HashMap::from([
+        ("heading_level_offset".to_string(), OptionValue::from(1)),
+        ("heading_overflow".to_string(), OptionValue::from("error")),
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        driver.convert("# title\n\n###### too deep", OutputFormat::Html),
+        Err(ConversionError {
+            messages: vec![
+                "line 3: heading level 7 of \"too deep\" is out of the allowed range (1-6)"
+                    .to_string()
+            ],
+        })
+    );
+    assert!(driver.parse_document("###### too deep").is_err());
+
+    // errors of one document do not affect others
+    assert_eq!(
+        driver.convert("# title", OutputFormat::Html),
+        Ok("<h2>title</h2>\n".to_string())
+    );
+}
+
+#[test]
+fn test_driver_warnings() {
+    let driver = MarkdownDriver::new(HashMap::from([(
+        "missing_alt_text".to_string(),
+        OptionValue::from("warning"),
+    )]))
+    .unwrap();
+
+    assert_eq!(
+        driver.convert_with_warnings("# title\n\n![](a.png)", OutputFormat::Html),
+        Ok((
+            "<h1>title</h1>\n<p><img src=\"a.png\" alt=\"\"></p>\n".to_string(),
+            vec!["line 3: image without alt text (a.png)".to_string()]
+        ))
+    );
+    assert_eq!(
+        driver.parse_document("![](a.png)").unwrap().warnings,
+        vec!["line 1: image without alt text (a.png)".to_string()]
+    );
+    assert_eq!(
+        driver.convert_with_warnings("![image](a.png)", OutputFormat::Html),
+        Ok((
+            "<p><img src=\"a.png\" alt=\"image\"></p>\n".to_string(),
+            vec![]
+        ))
+    );
+}
+
+#[test]
+fn test_syntax_switches() {
+    let src = "> quote\n\n    code\n\n| a |\n|---|\n| b |\n\n~~del~~ ![image](a.png) https://example.com \"quoted\" <b>html</b>";
+    let convert = |options: Vec<(&str, bool)>| {
+        let options = options
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), OptionValue::from(value)))
+            .collect();
+        MarkdownDriver::new(options)
+            .unwrap()
+            .convert(src, OutputFormat::Html)
+            .unwrap()
+    };
+
+    {
+        // all syntaxes are enabled by default
+        let html = convert(vec![("internal_domain_name", false)]);
+        assert_eq!(
+            html,
+            "<blockquote>\n<p>quote</p>\n</blockquote>\n<pre style=\"background-color:#ffffff;\">\n<span style=\"color:#323232;\">code\n</span></pre>\n<table class=\"table\">\n<thead>\n<tr>\n<th>a</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>b</td>\n</tr>\n</tbody>\n</table>\n<p><s>del</s> <img src=\"a.png\" alt=\"image\"> <a href=\"https://example.com\">example.com</a> “quoted” <b>html</b></p>\n"
+        );
+    }
+
+    {
+        // e.g. comment forms, which allow inline formatting and links only
+        let html = convert(vec![
+            ("internal_domain_name", false),
+            ("tables", false),
+            ("strikethrough", false),
+            ("linkify", false),
+            ("typographer", false),
+            ("html", false),
+            ("images", false),
+            ("code_blocks", false),
+            ("blockquotes", false),
+        ]);
+        assert_eq!(
+            html,
+            "<p>&gt; quote</p>\n<p>code</p>\n<p>| a |\n|---|\n| b |</p>\n<p>~~del~~ !<a href=\"a.png\">image</a> https://example.com &quot;quoted&quot; &lt;b&gt;html&lt;/b&gt;</p>\n"
+        );
+    }
+}
+
+#[test]
+fn test_presets() {
+    let src = "# title\n\n```rust\nfn main() {}\n```\n\n| a |\n|---|\n| b |\n\n~~del~~ <https://example.com/> \"quoted\"";
+    let convert = |preset: &str| {
+        MarkdownDriver::new(HashMap::from([(
+            "preset".to_string(),
+            OptionValue::from(preset),
+        )]))
+        .unwrap()
+        .convert(src, OutputFormat::Html)
+        .unwrap()
+    };
+
+    assert_eq!(
+        convert("commonmark"),
+        "<h1>title</h1>\n<pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n<p>| a |\n|---|\n| b |</p>\n<p>~~del~~ <a href=\"https://example.com/\">https://example.com/</a> &quot;quoted&quot;</p>\n"
+    );
+    assert_eq!(
+        convert("gfm"),
+        "<h1>title</h1>\n<pre><code class=\"language-rust\">fn main() {}\n</code></pre>\n<table>\n<thead>\n<tr>\n<th>a</th>\n</tr>\n</thead>\n<tbody>\n<tr>\n<td>b</td>\n</tr>\n</tbody>\n</table>\n<p><s>del</s> <a href=\"https://example.com/\">https://example.com/</a> &quot;quoted&quot;</p>\n"
+    );
+    assert_eq!(
+        convert("comment"),
+        "<p># title</p>\n<p><code>rust fn main() {} </code></p>\n<p>| a |\n|---|\n| b |</p>\n<p><s>del</s> <a href=\"https://example.com/\" target=\"_blank\" rel=\"noopener noreferrer nofollow ugc\">example.com</a> &quot;quoted&quot;</p>\n"
+    );
+    // the same as no options
+    assert_eq!(
+        convert("cms"),
+        MarkdownDriver::new(HashMap::new())
+            .unwrap()
+            .convert(src, OutputFormat::Html)
+            .unwrap()
+    );
+
+    // heading levels are limited even though the offset is disabled by the preset
+    let driver = MarkdownDriver::new(HashMap::from([
+        ("preset".to_string(), OptionValue::from("gfm")),
+        ("heading_max_level".to_string(), OptionValue::from(2)),
+        ("heading_overflow".to_string(), OptionValue::from("clamp")),
+    ]))
+    .unwrap();
+    assert_eq!(
+        driver.convert("# title\n\n### subtitle", OutputFormat::Html),
+        Ok("<h1>title</h1>\n<h2>subtitle</h2>\n".to_string())
+    );
+}
+
+#[test]
+fn test_collect_results() {
+    let driver = MarkdownDriver::new(HashMap::from([
+        ("heading_level_offset".to_string(), OptionValue::from(1)),
+        ("heading_overflow".to_string(), OptionValue::from("error")),
+    ]))
+    .unwrap();
+    let convert = |inputs: &[&str]| {
+        ConversionError::collect(
+            inputs
+                .iter()
+                .map(|input| driver.convert(input, OutputFormat::Html))
+                .collect(),
+        )
+    };
+
+    assert_eq!(
+        convert(&["# title 1", "## title 2"]),
+        Ok(vec![
+            "<h2>title 1</h2>\n".to_string(),
+            "<h3>title 2</h3>\n".to_string()
+        ])
+    );
+    assert_eq!(
+        convert(&["# title 1", "###### too deep", "text\n\n###### too deep"]),
+        Err(ConversionError {
+            messages: vec![
+                "document at index 1: line 1: heading level 7 of \"too deep\" is out of the allowed range (1-6)".to_string(),
+                "document at index 2: line 3: heading level 7 of \"too deep\" is out of the allowed range (1-6)".to_string(),
+            ],
+        })
+    );
+}
+
+#[test]
+fn test_lookup_urls() {
+    let driver = MarkdownDriver::new(HashMap::from([
+        ("file_link_annotation".to_string(), OptionValue::from(true)),
+        (
+            "internal_domain_name".to_string(),
+            OptionValue::from("example.com"),
+        ),
+    ]))
+    .unwrap();
+
+    assert_eq!(
+        driver
+            .lookup_urls("[hoge](/files/hoge.pdf) [page](/about) ![image](a.png) ![image](a.png)"),
+        Ok(LookupUrls {
+            file_sizes: vec!["/files/hoge.pdf".to_string()],
+            image_dimensions: vec!["a.png".to_string(), "a.png".to_string()],
+        })
+    );
 }
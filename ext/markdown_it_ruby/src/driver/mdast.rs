@@ -0,0 +1,426 @@
+use crate::extensions::heading_anchor::HeadingAnchor;
+use crate::extensions::heading_level_modification::PlainTextElement;
+use crate::extensions::image_figure::ImageFigure;
+use crate::extensions::link_with_target::{DisallowedLink, LinkWithTarget};
+use crate::extensions::table_decoration::{TableCaption, TableWrapper};
+use markdown_it::common::sourcemap::SourceWithLineStarts;
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::{Text, TextSpecial};
+use markdown_it::plugins::cmark::block::blockquote::Blockquote;
+use markdown_it::plugins::cmark::block::code::CodeBlock;
+use markdown_it::plugins::cmark::block::fence::CodeFence;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::hr::ThematicBreak;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::list::{BulletList, ListItem, OrderedList};
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::block::reference::Definition;
+use markdown_it::plugins::cmark::inline::backticks::CodeInline;
+use markdown_it::plugins::cmark::inline::emphasis::{Em, Strong};
+use markdown_it::plugins::cmark::inline::newline::{Hardbreak, Softbreak};
+use markdown_it::plugins::cmark::inline::{autolink::Autolink, image::Image, link::Link};
+use markdown_it::plugins::extra::linkify::Linkified;
+use markdown_it::plugins::extra::strikethrough::Strikethrough;
+use markdown_it::plugins::extra::tables::{
+    ColumnAlignment, Table, TableBody, TableCell, TableHead, TableRow,
+};
+use markdown_it::plugins::html::{html_block::HtmlBlock, html_inline::HtmlInline};
+use markdown_it::Node;
+use serde_json::{json, Map, Value};
+
+// mdast node types that can appear inside a paragraph (phrasing content)
+// https://github.com/syntax-tree/mdast#phrasingcontent
+const PHRASING_CONTENT: [&str; 10] = [
+    "break",
+    "delete",
+    "emphasis",
+    "html",
+    "image",
+    "inlineCode",
+    "link",
+    "strong",
+    "text",
+    "imageReference",
+];
+
+// serializes the AST into JSON, following the mdast schema
+// https://github.com/syntax-tree/mdast
+pub fn render(root: &Node) -> String {
+    let source = match root.cast::<Root>() {
+        Some(root) => root.content.as_str(),
+        None => "",
+    };
+    let mapping = SourceWithLineStarts::new(source);
+
+    let mut nodes = convert(root, &mapping);
+    match nodes.pop() {
+        Some(node) => node.to_string(),
+        None => json!({ "type": "root", "children": [] }).to_string(),
+    }
+}
+
+// converts a node into mdast nodes
+// (some nodes such as `thead` do not exist in mdast and are flattened into their children)
+fn convert(node: &Node, mapping: &SourceWithLineStarts) -> Vec<Value> {
+    let children = || convert_children(node, mapping);
+
+    let mut value = if node.is::<Root>() {
+        json!({ "type": "root", "children": children() })
+    } else if node.is::<Paragraph>() {
+        json!({ "type": "paragraph", "children": children() })
+    } else if let Some(heading) = node.cast::<ATXHeading>() {
+        json!({ "type": "heading", "depth": heading.level, "children": children() })
+    } else if let Some(heading) = node.cast::<SetextHeader>() {
+        json!({ "type": "heading", "depth": heading.level, "children": children() })
+    } else if let Some(element) = node.cast::<PlainTextElement>() {
+        // headings out of the accepted range are rendered as paragraphs
+        let mut contents = vec![json!({ "type": "text", "value": element.text })];
+        contents.extend(children());
+        json!({ "type": "paragraph", "children": merge_texts(contents) })
+    } else if node.is::<ImageFigure>() {
+        // figures do not exist in mdast (the caption is the title of the image)
+        json!({ "type": "paragraph", "children": children() })
+    } else if node.is::<Blockquote>() {
+        json!({ "type": "blockquote", "children": children() })
+    } else if node.is::<BulletList>() {
+        json!({ "type": "list", "ordered": false, "spread": is_loose(node), "children": children() })
+    } else if let Some(list) = node.cast::<OrderedList>() {
+        json!({
+            "type": "list",
+            "ordered": true,
+            "start": list.start,
+            "spread": is_loose(node),
+            "children": children(),
+        })
+    } else if node.is::<ListItem>() {
+        json!({
+            "type": "listItem",
+            "spread": node.children.iter().any(|child| child.is::<Paragraph>()),
+            "children": wrap_phrasing_content(children()),
+        })
+    } else if node.is::<ThematicBreak>() {
+        json!({ "type": "thematicBreak" })
+    } else if let Some(code) = node.cast::<CodeBlock>() {
+        json!({ "type": "code", "lang": null, "meta": null, "value": trim_code(&code.content) })
+    } else if let Some(fence) = node.cast::<CodeFence>() {
+        let info = fence.info.trim();
+        let (lang, meta) = match info.split_once(char::is_whitespace) {
+            Some((lang, meta)) => (Some(lang), Some(meta.trim())),
+            None if info.is_empty() => (None, None),
+            None => (Some(info), None),
+        };
+        json!({ "type": "code", "lang": lang, "meta": meta, "value": trim_code(&fence.content) })
+    } else if let Some(html) = node.cast::<HtmlBlock>() {
+        json!({ "type": "html", "value": html.content.trim_end_matches('\n') })
+    } else if let Some(html) = node.cast::<HtmlInline>() {
+        json!({ "type": "html", "value": html.content })
+    } else if let Some(text) = node.cast::<Text>() {
+        json!({ "type": "text", "value": text.content })
+    } else if let Some(text) = node.cast::<TextSpecial>() {
+        json!({ "type": "text", "value": text.content })
+    } else if node.is::<Softbreak>() {
+        json!({ "type": "text", "value": "\n" })
+    } else if node.is::<Hardbreak>() {
+        json!({ "type": "break" })
+    } else if node.is::<Em>() {
+        json!({ "type": "emphasis", "children": children() })
+    } else if node.is::<Strong>() {
+        json!({ "type": "strong", "children": children() })
+    } else if node.is::<Strikethrough>() {
+        json!({ "type": "delete", "children": children() })
+    } else if node.is::<CodeInline>() {
+        json!({ "type": "inlineCode", "value": node.collect_text() })
+    } else if let Some(link) = node.cast::<LinkWithTarget>() {
+        let mut value = json!({
+            "type": "link",
+            "url": link.url,
+            "title": link.title,
+            "children": children(),
+        });
+        let mut properties = Map::new();
+        if let Some(target) = link.target.as_ref() {
+            properties.insert("target".to_string(), json!(target));
+        }
+        if let Some(rel) = link.rel.as_ref() {
+            properties.insert("rel".to_string(), json!(rel));
+        }
+        if let Some(file) = link.file.as_ref() {
+            properties.insert("className".to_string(), json!([file.class]));
+        }
+        add_properties(&mut value, properties);
+        value
+    } else if node.is::<DisallowedLink>() {
+        // links with disallowed url schemes are kept as their plain contents
+        return children();
+    } else if let Some(link) = node.cast::<Link>() {
+        json!({ "type": "link", "url": link.url, "title": link.title, "children": children() })
+    } else if let Some(autolink) = node.cast::<Autolink>() {
+        json!({ "type": "link", "url": autolink.url, "title": null, "children": children() })
+    } else if let Some(linkified) = node.cast::<Linkified>() {
+        json!({ "type": "link", "url": linkified.url, "title": null, "children": children() })
+    } else if let Some(image) = node.cast::<Image>() {
+        json!({ "type": "image", "url": image.url, "title": image.title, "alt": node.collect_text() })
+    } else if let Some(table) = node.cast::<Table>() {
+        let align: Vec<Value> = table
+            .alignments
+            .iter()
+            .map(|alignment| match alignment {
+                ColumnAlignment::None => Value::Null,
+                ColumnAlignment::Left => json!("left"),
+                ColumnAlignment::Right => json!("right"),
+                ColumnAlignment::Center => json!("center"),
+            })
+            .collect();
+        json!({ "type": "table", "align": align, "children": children() })
+    } else if node.is::<TableWrapper>() {
+        // scrollable containers are for html only
+        return children();
+    } else if node.is::<TableCaption>() {
+        // mdast tables have no captions
+        return Vec::new();
+    } else if node.is::<TableHead>() || node.is::<TableBody>() {
+        return children();
+    } else if node.is::<TableRow>() {
+        json!({ "type": "tableRow", "children": children() })
+    } else if node.is::<TableCell>() {
+        json!({ "type": "tableCell", "children": children() })
+    } else if node.is::<HeadingAnchor>() {
+        // self-links are decorations only (ids of headings are kept in `data.hProperties`)
+        return Vec::new();
+    } else if node.is::<Definition>() {
+        // references are already resolved into links/images
+        return Vec::new();
+    } else {
+        // nodes unknown to mdast (e.g. added by other plugins) are kept as rendered html
+        json!({ "type": "html", "value": node.render() })
+    };
+
+    // html attributes added by extensions (e.g. table class)
+    let properties: Map<String, Value> = node
+        .attrs
+        .iter()
+        .map(|(key, value)| (key.to_string(), json!(value)))
+        .collect();
+    add_properties(&mut value, properties);
+
+    if let Some(position) = position(node, mapping) {
+        value["position"] = position;
+    }
+
+    vec![value]
+}
+
+fn convert_children(node: &Node, mapping: &SourceWithLineStarts) -> Vec<Value> {
+    let children = node
+        .children
+        .iter()
+        .flat_map(|child| convert(child, mapping))
+        .collect();
+    merge_texts(children)
+}
+
+// mdast points are 1-based, and the end point is exclusive
+fn position(node: &Node, mapping: &SourceWithLineStarts) -> Option<Value> {
+    // softbreaks point at the line ending, which has no meaningful column
+    if node.is::<Softbreak>() {
+        return None;
+    }
+
+    let srcmap = node.srcmap?;
+    let ((start_line, start_column), (end_line, end_column)) = srcmap.get_positions(mapping);
+
+    Some(json!({
+        "start": { "line": start_line, "column": start_column.max(1) },
+        "end": { "line": end_line, "column": end_column + 1 },
+    }))
+}
+
+fn add_properties(value: &mut Value, properties: Map<String, Value>) {
+    if properties.is_empty() {
+        return;
+    }
+
+    let data = value
+        .as_object_mut()
+        .unwrap()
+        .entry("data")
+        .or_insert_with(|| json!({ "hProperties": {} }));
+    if let Some(existing) = data["hProperties"].as_object_mut() {
+        existing.extend(properties);
+    }
+}
+
+// adjacent text nodes (e.g. text and softbreaks) are merged into one, as mdast does
+fn merge_texts(nodes: Vec<Value>) -> Vec<Value> {
+    let mut merged: Vec<Value> = Vec::with_capacity(nodes.len());
+
+    for node in nodes {
+        if let Some(last) = merged.last_mut() {
+            if last["type"] == "text" && node["type"] == "text" {
+                let value = format!(
+                    "{}{}",
+                    last["value"].as_str().unwrap_or_default(),
+                    node["value"].as_str().unwrap_or_default()
+                );
+                last["value"] = json!(value);
+                if !last["position"].is_null() && !node["position"].is_null() {
+                    last["position"]["end"] = node["position"]["end"].clone();
+                }
+                continue;
+            }
+        }
+        merged.push(node);
+    }
+    merged
+}
+
+// items of tight lists contain inline nodes directly (paragraphs are omitted),
+// while mdast list items always contain flow content
+fn wrap_phrasing_content(nodes: Vec<Value>) -> Vec<Value> {
+    let mut wrapped: Vec<Value> = Vec::new();
+    let mut phrasing: Vec<Value> = Vec::new();
+
+    for node in nodes {
+        if PHRASING_CONTENT.contains(&node["type"].as_str().unwrap_or_default()) {
+            phrasing.push(node);
+        } else {
+            if !phrasing.is_empty() {
+                let children = std::mem::take(&mut phrasing);
+                wrapped.push(json!({ "type": "paragraph", "children": children }));
+            }
+            wrapped.push(node);
+        }
+    }
+    if !phrasing.is_empty() {
+        wrapped.push(json!({ "type": "paragraph", "children": phrasing }));
+    }
+    wrapped
+}
+
+fn is_loose(list: &Node) -> bool {
+    list.children
+        .iter()
+        .any(|item| item.children.iter().any(|child| child.is::<Paragraph>()))
+}
+
+fn trim_code(content: &str) -> &str {
+    content.strip_suffix('\n').unwrap_or(content)
+}
+
+#[test]
+fn test_mdast() {
+    let mut md = markdown_it::MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it::plugins::extra::tables::add(&mut md);
+    crate::extensions::link_with_target::add(&mut md);
+    crate::extensions::table_decoration::add(&mut md);
+
+    {
+        // headings, paragraphs and links
+        let src = "## title\n\nsome *text*\n[link](https://example.com)";
+        let mdast: Value = serde_json::from_str(&render(&md.parse(src))).unwrap();
+
+        assert_eq!(
+            mdast,
+            json!({
+                "type": "root",
+                "children": [
+                    {
+                        "type": "heading",
+                        "depth": 2,
+                        "children": [{
+                            "type": "text",
+                            "value": "title",
+                            "position": { "start": { "line": 1, "column": 4 }, "end": { "line": 1, "column": 9 } },
+                        }],
+                        "position": { "start": { "line": 1, "column": 1 }, "end": { "line": 1, "column": 9 } },
+                    },
+                    {
+                        "type": "paragraph",
+                        "children": [
+                            {
+                                "type": "text",
+                                "value": "some ",
+                                "position": { "start": { "line": 3, "column": 1 }, "end": { "line": 3, "column": 6 } },
+                            },
+                            {
+                                "type": "emphasis",
+                                "children": [{
+                                    "type": "text",
+                                    "value": "text",
+                                    "position": { "start": { "line": 3, "column": 7 }, "end": { "line": 3, "column": 11 } },
+                                }],
+                                "position": { "start": { "line": 3, "column": 6 }, "end": { "line": 3, "column": 12 } },
+                            },
+                            { "type": "text", "value": "\n" },
+                            {
+                                "type": "link",
+                                "url": "https://example.com",
+                                "title": null,
+                                "children": [{
+                                    "type": "text",
+                                    "value": "link",
+                                    "position": { "start": { "line": 4, "column": 2 }, "end": { "line": 4, "column": 6 } },
+                                }],
+                                "data": { "hProperties": { "target": "_blank", "rel": "noopener noreferrer" } },
+                                "position": { "start": { "line": 4, "column": 1 }, "end": { "line": 4, "column": 28 } },
+                            },
+                        ],
+                        "position": { "start": { "line": 3, "column": 1 }, "end": { "line": 4, "column": 28 } },
+                    },
+                ],
+                "position": { "start": { "line": 1, "column": 1 }, "end": { "line": 4, "column": 28 } },
+            })
+        );
+    }
+
+    {
+        // tight lists
+        let src = "- item 1\n- item 2";
+        let mdast: Value = serde_json::from_str(&render(&md.parse(src))).unwrap();
+        let list = &mdast["children"][0];
+
+        assert_eq!(list["type"], "list");
+        assert_eq!(list["ordered"], false);
+        assert_eq!(list["spread"], false);
+        assert_eq!(list["children"][0]["type"], "listItem");
+        assert_eq!(list["children"][0]["children"][0]["type"], "paragraph");
+        assert_eq!(
+            list["children"][0]["children"][0]["children"][0]["value"],
+            "item 1"
+        );
+    }
+
+    {
+        // decorated tables
+        let src = "| left | right |\n|:-----|------:|\n| a | b |";
+        let mdast: Value = serde_json::from_str(&render(&md.parse(src))).unwrap();
+        let table = &mdast["children"][0];
+
+        assert_eq!(table["type"], "table");
+        assert_eq!(table["align"], json!(["left", "right"]));
+        assert_eq!(
+            table["data"],
+            json!({ "hProperties": { "class": "table" } })
+        );
+        assert_eq!(table["children"].as_array().unwrap().len(), 2);
+        assert_eq!(table["children"][1]["type"], "tableRow");
+        assert_eq!(table["children"][1]["children"][1]["type"], "tableCell");
+        assert_eq!(
+            table["children"][1]["children"][1]["children"][0]["value"],
+            "b"
+        );
+    }
+
+    {
+        // code blocks
+        let src = "```ruby linenos\nputs 1\n```";
+        let mdast: Value = serde_json::from_str(&render(&md.parse(src))).unwrap();
+        let code = &mdast["children"][0];
+
+        assert_eq!(code["type"], "code");
+        assert_eq!(code["lang"], "ruby");
+        assert_eq!(code["meta"], "linenos");
+        assert_eq!(code["value"], "puts 1");
+    }
+}
@@ -1,119 +1,722 @@
 use markdown_it::parser::extset::MarkdownItExt;
 use markdown_it::MarkdownIt;
-use std::collections::HashMap;
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 use url::Url;
 
+// an option value given from Ruby (`true`/`false`, Integer, String/Symbol, Array, Hash)
+#[derive(Debug, Clone, PartialEq)]
+pub enum OptionValue {
+    Bool(bool),
+    Integer(i64),
+    String(String),
+    List(Vec<OptionValue>),
+    Map(BTreeMap<String, OptionValue>),
+}
+
+impl fmt::Display for OptionValue {
+    // same as `inspect` in Ruby, to be used in error messages
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Integer(value) => write!(f, "{}", value),
+            Self::String(value) => write!(f, "{:?}", value),
+            Self::List(values) => {
+                let values: Vec<String> = values.iter().map(|value| value.to_string()).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+            Self::Map(values) => {
+                let values: Vec<String> = values
+                    .iter()
+                    .map(|(key, value)| format!("{:?} => {}", key, value))
+                    .collect();
+                write!(f, "{{{}}}", values.join(", "))
+            }
+        }
+    }
+}
+
+impl From<&str> for OptionValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<bool> for OptionValue {
+    fn from(value: bool) -> Self {
+        Self::Bool(value)
+    }
+}
+
+impl From<i64> for OptionValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum OptionKind {
+    Bool,
+    Integer { min: i64, max: i64 },
+    String,
+    // one of the listed strings
+    Choice(&'static [&'static str]),
+    // an array of strings (a comma separated string is also accepted)
+    List,
+    // an array of integers (a comma separated string is also accepted)
+    IntegerList { min: i64, max: i64 },
+    // a hash of strings to the values of the kind (e.g. file sizes by path)
+    Map(&'static OptionKind),
+}
+
+// checks values of the kind further, returning what is wrong with them
+type OptionCheck = fn(&OptionValue) -> Result<(), String>;
+
 #[derive(Debug)]
+struct OptionSpec {
+    name: &'static str,
+    kind: OptionKind,
+    // `false` turns the feature off (e.g. `table_class_name: false`)
+    disableable: bool,
+    check: Option<OptionCheck>,
+}
+
+impl OptionSpec {
+    const fn new(name: &'static str, kind: OptionKind) -> Self {
+        Self {
+            name,
+            kind,
+            disableable: false,
+            check: None,
+        }
+    }
+
+    const fn or_false(self) -> Self {
+        Self {
+            disableable: true,
+            ..self
+        }
+    }
+
+    const fn checked_by(self, check: OptionCheck) -> Self {
+        Self {
+            check: Some(check),
+            ..self
+        }
+    }
+}
+
+// e.g. `*.example.com` or `https://example.com`
+fn check_domain_names(value: &OptionValue) -> Result<(), String> {
+    let OptionValue::List(domain_names) = value else {
+        return Ok(());
+    };
+    for domain_name in domain_names {
+        if let OptionValue::String(pattern) = domain_name {
+            if DomainPattern::new(pattern).is_none() {
+                return Err(format!("{} is not a valid domain name", domain_name));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn check_absolute_url(value: &OptionValue) -> Result<(), String> {
+    match value {
+        OptionValue::String(url) if Url::parse(url).map_or(true, |url| url.cannot_be_a_base()) => {
+            Err(format!("{:?} is not an absolute url", url))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_image_proxy_url(value: &OptionValue) -> Result<(), String> {
+    match value {
+        OptionValue::String(template) if !template.contains("{url}") => {
+            Err(format!("{:?} does not contain {{url}}", template))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_image_srcset_template(value: &OptionValue) -> Result<(), String> {
+    match value {
+        OptionValue::String(template) if !template.contains("{width}") => {
+            Err(format!("{:?} does not contain {{width}}", template))
+        }
+        _ => Ok(()),
+    }
+}
+
+const HEADING_LEVEL: OptionKind = OptionKind::Integer { min: 1, max: 6 };
+const BYTE_SIZE: OptionKind = OptionKind::Integer {
+    min: 0,
+    max: i64::MAX,
+};
+// `{ "width" => 800, "height" => 600 }`
+const IMAGE_DIMENSION: OptionKind = OptionKind::Map(&OptionKind::Integer {
+    min: 1,
+    max: i64::MAX,
+});
+
+// all the options accepted, anything else is rejected as a typo
+const OPTION_SPECS: &[OptionSpec] = &[
+    // a domain name, or a list of them (wildcards such as `*.example.com` are accepted)
+    OptionSpec::new("internal_domain_name", OptionKind::List)
+        .or_false()
+        .checked_by(check_domain_names),
+    OptionSpec::new("internal_domain_exclusions", OptionKind::List).checked_by(check_domain_names),
+    // how urls such as `google.com/foo` are handled, where those starting with a name ending in
+    // a top level domain are taken as hostnames (except for common file extensions such as `.md`
+    // and `.zip`, unless the name starts with `www.`), so `notes.page` is taken as a hostname
+    OptionSpec::new(
+        "scheme_less_urls",
+        OptionKind::Choice(&["internal", "external", "https"]),
+    ),
+    OptionSpec::new("external_link_target", OptionKind::String).or_false(),
+    OptionSpec::new("external_link_rel", OptionKind::String).or_false(),
+    // a visually hidden text and/or an icon for links opening new windows
+    OptionSpec::new("external_link_hint", OptionKind::Bool),
+    OptionSpec::new("external_link_hint_text", OptionKind::String),
+    OptionSpec::new("external_link_hint_class", OptionKind::String),
+    OptionSpec::new("external_link_icon_class", OptionKind::String),
+    // file types and sizes of links to documents (e.g. `（PDF：123KB）`)
+    OptionSpec::new("file_link_annotation", OptionKind::Bool),
+    OptionSpec::new("file_link_extensions", OptionKind::List),
+    OptionSpec::new("file_link_class", OptionKind::String),
+    OptionSpec::new("file_link_label", OptionKind::String),
+    OptionSpec::new("file_link_label_without_size", OptionKind::String),
+    // bytes by path (a callback is resolved into this on the Ruby side)
+    OptionSpec::new("file_sizes", OptionKind::Map(&BYTE_SIZE)),
+    // absolute urls which relative links and images are resolved against
+    OptionSpec::new("base_url", OptionKind::String).checked_by(check_absolute_url),
+    OptionSpec::new("asset_base_url", OptionKind::String).checked_by(check_absolute_url),
+    // images without alt text
+    OptionSpec::new(
+        "missing_alt_text",
+        OptionKind::Choice(&["allow", "placeholder", "warning", "error"]),
+    ),
+    OptionSpec::new("alt_text_placeholder", OptionKind::String),
+    // images standing alone in paragraphs rendered as figures, captioned by their titles
+    OptionSpec::new(
+        "image_figures",
+        OptionKind::Choice(&["titled", "standalone"]),
+    )
+    .or_false(),
+    OptionSpec::new("image_figure_class", OptionKind::String),
+    // html attributes of images
+    OptionSpec::new("image_lazy_loading", OptionKind::Bool),
+    OptionSpec::new("image_class", OptionKind::String),
+    // width and height by url (a callback is resolved into this on the Ruby side)
+    OptionSpec::new("image_dimensions", OptionKind::Map(&IMAGE_DIMENSION)),
+    // templates of urls take `{url}` as the url-encoded image url (e.g. `https%3A%2F%2Fexample.com%2Fa.jpg`)
+    //
+    // e.g. `https://resize.example.com/?w={width}&url={url}`, with widths such as `[320, 640, 1280]`,
+    // or `https://img.example.com/w{width}{path}`, where `{path}` is a relative url as written
+    // (images of absolute urls get no srcset with `{path}`)
+    OptionSpec::new("image_srcset_template", OptionKind::String)
+        .checked_by(check_image_srcset_template),
+    OptionSpec::new(
+        "image_srcset_widths",
+        OptionKind::IntegerList {
+            min: 1,
+            max: i64::MAX,
+        },
+    ),
+    OptionSpec::new("image_sizes", OptionKind::String),
+    // e.g. `https://img.example.jp/proxy?url={url}&sig={hmac}` for images of other sites,
+    // where `{hmac}` is the HMAC-SHA256 signature of the url by `image_proxy_key`
+    OptionSpec::new("image_proxy_url", OptionKind::String).checked_by(check_image_proxy_url),
+    OptionSpec::new("image_proxy_key", OptionKind::String),
+    OptionSpec::new(
+        "heading_level_offset",
+        OptionKind::Integer { min: 0, max: 6 },
+    )
+    .or_false(),
+    OptionSpec::new("heading_min_level", HEADING_LEVEL),
+    OptionSpec::new("heading_max_level", HEADING_LEVEL),
+    OptionSpec::new(
+        "heading_overflow",
+        OptionKind::Choice(&["literal", "clamp", "paragraph", "strong", "error"]),
+    ),
+    OptionSpec::new("heading_anchors", OptionKind::Bool),
+    OptionSpec::new("heading_anchor_prefix", OptionKind::String),
+    OptionSpec::new("heading_anchor_link", OptionKind::Bool),
+    OptionSpec::new("heading_anchor_class", OptionKind::String),
+    OptionSpec::new("table_of_contents", OptionKind::Bool),
+    OptionSpec::new("toc_min_level", HEADING_LEVEL),
+    OptionSpec::new("toc_max_level", HEADING_LEVEL),
+    OptionSpec::new("toc_class", OptionKind::String),
+    OptionSpec::new("table_class_name", OptionKind::String).or_false(),
+    // e.g. `table-responsive`, a container making wide tables scrollable
+    OptionSpec::new("table_wrapper_class", OptionKind::String),
+    OptionSpec::new("table_head_class", OptionKind::String),
+    OptionSpec::new("table_body_class", OptionKind::String),
+    OptionSpec::new("table_row_class", OptionKind::String),
+    OptionSpec::new("table_header_cell_class", OptionKind::String),
+    OptionSpec::new("table_cell_class", OptionKind::String),
+    // captions taken from paragraphs preceding tables (e.g. `Table: 令和6年度の予算`)
+    OptionSpec::new("table_captions", OptionKind::Bool),
+    OptionSpec::new("table_caption_prefix", OptionKind::String),
+    OptionSpec::new("html", OptionKind::Choice(&["allow", "escape", "sanitize"])).or_false(),
+    OptionSpec::new("html_allowed_tags", OptionKind::List),
+    OptionSpec::new("html_allowed_attributes", OptionKind::List),
+    OptionSpec::new("allowed_url_schemes", OptionKind::List),
+    OptionSpec::new(
+        "disallowed_link",
+        OptionKind::Choice(&["text", "empty_href"]),
+    ),
+    OptionSpec::new(
+        "preset",
+        OptionKind::Choice(&["commonmark", "gfm", "comment", "cms"]),
+    ),
+    // markdown syntaxes, all enabled by default
+    OptionSpec::new("headings", OptionKind::Bool),
+    OptionSpec::new("tables", OptionKind::Bool),
+    OptionSpec::new("strikethrough", OptionKind::Bool),
+    OptionSpec::new("linkify", OptionKind::Bool),
+    OptionSpec::new("typographer", OptionKind::Bool),
+    OptionSpec::new("images", OptionKind::Bool),
+    OptionSpec::new("code_blocks", OptionKind::Bool),
+    OptionSpec::new("syntax_highlighting", OptionKind::Bool),
+    OptionSpec::new("blockquotes", OptionKind::Bool),
+    OptionSpec::new("beautify_links", OptionKind::Bool),
+];
+
+// pairs of options where the former must not exceed the latter
+const OPTION_RANGES: [(&str, &str); 2] = [
+    ("heading_min_level", "heading_max_level"),
+    ("toc_min_level", "toc_max_level"),
+];
+
+// invalid options given from Ruby (raised as `MarkdownIt::Error`)
+#[derive(Debug, PartialEq)]
+pub struct OptionError {
+    pub messages: Vec<String>,
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.messages.join("\n"))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct MarkdonwItOptions {
-    options: HashMap<String, String>,
+    options: HashMap<String, OptionValue>,
 }
 
+// domains of the site, links to which are not opened in new tabs
 #[derive(Debug, Clone)]
 pub struct InternalDomain {
-    base_url: Url,
+    domains: Vec<DomainPattern>,
+    // e.g. a subdomain hosting third-party contents
+    exclusions: Vec<DomainPattern>,
+}
+
+#[derive(Debug, Clone)]
+enum DomainPattern {
+    // `example.com`, which matches its subdomains as well (e.g. `www.example.com`)
+    Name(String),
+    // `*.example.com` or `city-*.example.jp`, where `*` matches characters within one label
+    Wildcard(Regex),
 }
 
 impl MarkdownItExt for MarkdonwItOptions {}
-impl Clone for MarkdonwItOptions {
-    fn clone(&self) -> Self {
-        Self {
-            options: self.options.clone(),
+
+impl MarkdonwItOptions {
+    // validates the options against `OPTION_SPECS`
+    pub fn new(options: HashMap<String, OptionValue>) -> Result<Self, OptionError> {
+        let mut keys: Vec<&String> = options.keys().collect();
+        keys.sort();
+
+        let mut messages = Vec::new();
+        let mut validated = HashMap::new();
+        for key in keys {
+            let value = &options[key];
+            match OPTION_SPECS.iter().find(|spec| spec.name == key) {
+                None => messages.push(Self::unknown_option_message(key)),
+                Some(spec) => match Self::validate(spec, value) {
+                    Ok(value) => {
+                        validated.insert(key.clone(), value);
+                    }
+                    Err(message) => messages.push(message),
+                },
+            }
+        }
+
+        // options given explicitly take precedence over the preset
+        if let Some(OptionValue::String(preset)) = validated.get("preset").cloned() {
+            for (key, value) in Self::preset_options(&preset) {
+                validated.entry(key.to_string()).or_insert(value);
+            }
+        }
+
+        let options = Self { options: validated };
+        for (min, max) in OPTION_RANGES {
+            if let (Some(OptionValue::Integer(min_value)), Some(OptionValue::Integer(max_value))) =
+                (options.get_option(min), options.get_option(max))
+            {
+                if min_value > max_value {
+                    messages.push(format!(
+                        "{} ({}) must not be greater than {} ({})",
+                        min, min_value, max, max_value
+                    ));
+                }
+            }
+        }
+        if let Some(template) = options.get_string("image_proxy_url") {
+            if template.contains("{hmac}") && options.get_option("image_proxy_key").is_none() {
+                messages.push("image_proxy_key is required to sign image_proxy_url".to_string());
+            }
+        }
+
+        if messages.is_empty() {
+            Ok(options)
+        } else {
+            Err(OptionError { messages })
         }
     }
-}
-impl Default for MarkdonwItOptions {
-    fn default() -> Self {
-        Self {
-            options: HashMap::new(),
+
+    // option values implied by `preset`
+    // (`cms`, the default, is what this gem has always produced: html + cmark + extra plugins)
+    fn preset_options(preset: &str) -> Vec<(&'static str, OptionValue)> {
+        let switches: &[(&'static str, bool)] = match preset {
+            // plain CommonMark, without extensions of this gem nor markdown-it
+            "commonmark" => &[
+                ("internal_domain_name", false),
+                ("heading_level_offset", false),
+                ("table_class_name", false),
+                ("tables", false),
+                ("strikethrough", false),
+                ("linkify", false),
+                ("typographer", false),
+                ("syntax_highlighting", false),
+                ("beautify_links", false),
+            ],
+            // CommonMark with GitHub Flavored Markdown extensions (tables, strikethrough, autolinks)
+            "gfm" => &[
+                ("internal_domain_name", false),
+                ("heading_level_offset", false),
+                ("table_class_name", false),
+                ("typographer", false),
+                ("syntax_highlighting", false),
+                ("beautify_links", false),
+            ],
+            // user comments, allowing inline formatting and links only
+            "comment" => &[
+                ("html", false),
+                ("headings", false),
+                ("tables", false),
+                ("images", false),
+                ("code_blocks", false),
+                ("blockquotes", false),
+                ("typographer", false),
+            ],
+            _ => &[],
+        };
+        let mut options: Vec<(&'static str, OptionValue)> = switches
+            .iter()
+            .map(|(key, value)| (*key, OptionValue::from(*value)))
+            .collect();
+        if preset == "comment" {
+            // links posted by users are not endorsed by the site
+            options.push((
+                "external_link_rel",
+                OptionValue::from("noopener noreferrer nofollow ugc"),
+            ));
         }
+        options
     }
-}
 
-impl MarkdonwItOptions {
-    pub fn new(options: HashMap<String, String>) -> Self {
-        Self { options }
+    fn validate(spec: &OptionSpec, value: &OptionValue) -> Result<OptionValue, String> {
+        // `"false"` as well, for values from YAML or ENV
+        if spec.disableable
+            && (*value == OptionValue::Bool(false) || *value == OptionValue::from("false"))
+        {
+            return Ok(OptionValue::Bool(false));
+        }
+
+        let validated = Self::validate_kind(spec.kind, value);
+
+        // the option-specific check
+        if let (Some(check), Some(validated)) = (spec.check, &validated) {
+            check(validated)
+                .map_err(|message| format!("invalid value for {}: {}", spec.name, message))?;
+        }
+
+        validated.ok_or_else(|| {
+            format!(
+                "invalid value for {}: expected {}, got {}",
+                spec.name,
+                Self::expectation(spec),
+                value
+            )
+        })
+    }
+
+    fn validate_kind(kind: OptionKind, value: &OptionValue) -> Option<OptionValue> {
+        match (kind, value) {
+            (OptionKind::Bool, OptionValue::Bool(_)) => Some(value.clone()),
+            // strings from YAML or ENV (e.g. `"true"`, `"2"`) are coerced into their types
+            (OptionKind::Bool, OptionValue::String(string)) => match string.trim() {
+                "true" => Some(OptionValue::Bool(true)),
+                "false" => Some(OptionValue::Bool(false)),
+                _ => None,
+            },
+            (OptionKind::Integer { min, max }, OptionValue::Integer(integer))
+                if (min..=max).contains(integer) =>
+            {
+                Some(value.clone())
+            }
+            (OptionKind::Integer { min, max }, OptionValue::String(string)) => string
+                .trim()
+                .parse::<i64>()
+                .ok()
+                .filter(|integer| (min..=max).contains(integer))
+                .map(OptionValue::Integer),
+            (OptionKind::String, OptionValue::String(_)) => Some(value.clone()),
+            (OptionKind::Choice(choices), OptionValue::String(string))
+                if choices.contains(&string.as_str()) =>
+            {
+                Some(value.clone())
+            }
+            (OptionKind::List, OptionValue::String(string)) => Some(OptionValue::List(
+                string
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(OptionValue::from)
+                    .collect(),
+            )),
+            (OptionKind::List, OptionValue::List(items))
+                if items
+                    .iter()
+                    .all(|item| matches!(item, OptionValue::String(_))) =>
+            {
+                Some(value.clone())
+            }
+            (OptionKind::IntegerList { min, max }, OptionValue::String(string)) => string
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| {
+                    item.parse::<i64>()
+                        .ok()
+                        .filter(|integer| (min..=max).contains(integer))
+                        .map(OptionValue::Integer)
+                })
+                .collect::<Option<Vec<_>>>()
+                .map(OptionValue::List),
+            (OptionKind::IntegerList { min, max }, OptionValue::List(items))
+                if items.iter().all(
+                    |item| matches!(item, OptionValue::Integer(integer) if (min..=max).contains(integer)),
+                ) =>
+            {
+                Some(value.clone())
+            }
+            (OptionKind::Map(kind), OptionValue::Map(values)) => values
+                .iter()
+                .map(|(key, value)| Some((key.clone(), Self::validate_kind(*kind, value)?)))
+                .collect::<Option<BTreeMap<_, _>>>()
+                .map(OptionValue::Map),
+            _ => None,
+        }
+    }
+
+    // e.g. `an integer between 0 and 6 or false`
+    fn expectation(spec: &OptionSpec) -> String {
+        let expectation = Self::describe(spec.kind);
+        if spec.disableable {
+            format!("{} or false", expectation)
+        } else {
+            expectation
+        }
+    }
+
+    fn describe(kind: OptionKind) -> String {
+        match kind {
+            OptionKind::Bool => "true or false".to_string(),
+            OptionKind::Integer { min, max: i64::MAX } => format!("an integer of {} or more", min),
+            OptionKind::Integer { min, max } => format!("an integer between {} and {}", min, max),
+            OptionKind::String => "a string".to_string(),
+            OptionKind::Choice(choices) => {
+                let choices: Vec<String> = choices
+                    .iter()
+                    .map(|choice| format!("{:?}", choice))
+                    .collect();
+                format!("one of {}", choices.join(", "))
+            }
+            OptionKind::List => "an array of strings".to_string(),
+            OptionKind::IntegerList { min, max } => format!(
+                "an array of {}",
+                Self::describe(OptionKind::Integer { min, max }).replacen(
+                    "an integer",
+                    "integers",
+                    1
+                )
+            ),
+            OptionKind::Map(kind) => format!("a hash of strings to {}", Self::describe(*kind)),
+        }
+    }
+
+    fn unknown_option_message(key: &str) -> String {
+        // suggest the closest option name for typos
+        let suggestion = OPTION_SPECS
+            .iter()
+            .map(|spec| (Self::edit_distance(key, spec.name), spec.name))
+            .filter(|(distance, _)| *distance <= 3)
+            .min();
+        match suggestion {
+            Some((_, name)) => format!("unknown option: {} (did you mean {}?)", key, name),
+            None => format!("unknown option: {}", key),
+        }
+    }
+
+    // levenshtein distance
+    fn edit_distance(a: &str, b: &str) -> usize {
+        let b: Vec<char> = b.chars().collect();
+        let mut distances: Vec<usize> = (0..=b.len()).collect();
+        for (i, a_char) in a.chars().enumerate() {
+            let mut previous = distances[0];
+            distances[0] = i + 1;
+            for (j, b_char) in b.iter().enumerate() {
+                let current = distances[j + 1];
+                distances[j + 1] = if a_char == *b_char {
+                    previous
+                } else {
+                    1 + previous.min(current).min(distances[j])
+                };
+                previous = current;
+            }
+        }
+        distances[b.len()]
     }
 
     pub fn add(self, md: &mut MarkdownIt) {
         md.ext.insert::<Self>(self);
     }
 
+    // the value of `true`/`false` options, otherwise `default`
+    // (options such as `table_class_name` are enabled by their values, and disabled by `false`)
     pub fn is_enabled(&self, key: &str, default: bool) -> bool {
-        match self.get_option_or_default(key, "_NOT_APPLICABLE").as_str() {
-            "true" => true,
-            "false" => false,
+        match self.options.get(key) {
+            Some(OptionValue::Bool(value)) => *value,
             _ => default,
         }
     }
 
-    pub fn get_option(&self, key: &str) -> Option<&String> {
+    pub fn get_option(&self, key: &str) -> Option<&OptionValue> {
         self.options.get(key)
     }
 
+    // for string options, `None` unless given
+    pub fn get_string(&self, key: &str) -> Option<String> {
+        match self.options.get(key) {
+            Some(OptionValue::String(value)) => Some(value.clone()),
+            _ => None,
+        }
+    }
+
+    // for string options
     pub fn get_option_or_default(&self, key: &str, default: &str) -> String {
-        self.options
-            .get(key)
-            .cloned()
-            .unwrap_or_else(|| default.to_string())
+        self.get_string(key).unwrap_or_else(|| default.to_string())
+    }
+
+    pub fn get_integer_or_default(&self, key: &str, default: i64) -> i64 {
+        match self.options.get(key) {
+            Some(OptionValue::Integer(value)) => *value,
+            _ => default,
+        }
+    }
+
+    pub fn get_list_or_default(&self, key: &str, default: &[&str]) -> Vec<String> {
+        match self.options.get(key) {
+            Some(OptionValue::List(values)) => values
+                .iter()
+                .filter_map(|value| match value {
+                    OptionValue::String(value) => Some(value.clone()),
+                    _ => None,
+                })
+                .collect(),
+            _ => default.iter().map(|value| value.to_string()).collect(),
+        }
+    }
+
+    // for arrays of integers
+    pub fn get_integer_list(&self, key: &str) -> Vec<i64> {
+        match self.options.get(key) {
+            Some(OptionValue::List(values)) => values
+                .iter()
+                .filter_map(|value| match value {
+                    OptionValue::Integer(value) => Some(*value),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    // for hashes of strings to integers (e.g. `file_sizes`)
+    pub fn get_integer_map(&self, key: &str) -> HashMap<String, i64> {
+        match self.options.get(key) {
+            Some(OptionValue::Map(values)) => values
+                .iter()
+                .filter_map(|(key, value)| match value {
+                    OptionValue::Integer(value) => Some((key.clone(), *value)),
+                    _ => None,
+                })
+                .collect(),
+            _ => HashMap::new(),
+        }
     }
 
     pub fn internal_domain(&self) -> Option<InternalDomain> {
-        let domain_name = self.get_option("internal_domain_name");
-        match domain_name {
-            None => None,
-            Some(domain_name) => InternalDomain::new(domain_name.clone()),
+        let domain_names = self.get_list_or_default("internal_domain_name", &[]);
+        if domain_names.is_empty() {
+            return None;
         }
+        let exclusions = self.get_list_or_default("internal_domain_exclusions", &[]);
+        InternalDomain::with_exclusions(&domain_names, &exclusions)
     }
 }
 
 impl InternalDomain {
     fn new(domain_name: String) -> Option<Self> {
-        if let Ok(base_url) = Url::parse(domain_name.as_str()) {
-            if base_url.domain().is_some() {
-                return Some(InternalDomain { base_url });
-            }
-        }
-
-        let domain_with_scheme = format!("https://{}", domain_name);
-        if let Ok(base_url) = Url::parse(&domain_with_scheme) {
-            if base_url.domain().is_some() {
-                return Some(InternalDomain { base_url });
-            }
-        }
+        Self::with_exclusions(&[domain_name], &[])
+    }
 
-        None
+    fn with_exclusions(domain_names: &[String], exclusions: &[String]) -> Option<Self> {
+        let patterns = |names: &[String]| -> Option<Vec<DomainPattern>> {
+            names.iter().map(|name| DomainPattern::new(name)).collect()
+        };
+        Some(InternalDomain {
+            domains: patterns(domain_names)?,
+            exclusions: patterns(exclusions)?,
+        })
     }
 
     pub fn matches(&self, url: &str) -> bool {
-        // let url_parser = Url::options().base_url(Some(&self.base_url));
-        let internal_domain_name = self.base_url.domain().unwrap();
-
         // url with scheme
         if Self::check_scheme(url) {
-            return self.check_internal_domain(url, internal_domain_name, false);
+            return match Url::parse(url) {
+                Ok(url) => url
+                    .domain()
+                    .is_some_and(|domain| self.check_internal_domain(domain)),
+                Err(_) => false,
+            };
         }
 
         // 一部の「schemeなし、パス付きURL表記」をヒューリスティックに判定できるが
-        // 仕様が複雑になるためいったんやらない
+        // 仕様が複雑になるため、ここではやらない（`scheme_less_urls` オプションで有効にする）
         // （google.com/foo は外部サイトとして判定しうるが、google.com は foo.pdf
         // と判別がつかないため内部リンク扱いとなり、ルールがわかりにくくなるため）
-        // // possiblly a domain name
-        // // heuristics: if the url contains a dot, it's a relative path with domain
-        // // we do not regard them as internal links
-        // // e.g. google.com/foo/bar
-        // let domain_regex = regex!(r"^[a-zA-Z0-9-]+(\.[a-zA-Z0-9-]+)+/");
-        // if domain_regex.is_match(url) {
-        //     println!(
-        //         "check #2 - url: {}, internal_domain_name: {}",
-        //         url, internal_domain_name
-        //     );
-        //     return false;
-        // }
 
         // should be a relative path
         // NOTE: google.com（外部サイト）, google.pdf（ファイル名）は区別がつかないため全て内部リンク扱いとする
         // 外部サイトの場合はスキームをつけること
-        self.check_internal_domain(&url, internal_domain_name, true)
+        self.check_relative_url(url)
     }
 
     fn check_scheme(url: &str) -> bool {
@@ -125,99 +728,324 @@ impl InternalDomain {
         false
     }
 
-    fn check_internal_domain(
-        &self,
-        url: &str,
-        internal_domain_name: &str,
-        is_base_url_required: bool,
-    ) -> bool {
-        let url = if is_base_url_required {
-            Url::options().base_url(Some(&self.base_url)).parse(url)
-        } else {
-            Url::parse(url)
-        };
-        if let Ok(url) = url {
-            if let Some(domain) = url.domain() {
-                // for `example.com`, `foo.example.com` is also considered as an internal link
-                if domain == internal_domain_name
-                    || domain.ends_with(format!(".{}", internal_domain_name).as_str())
-                {
-                    return true;
-                }
-            }
+    // urls without scheme are resolved against two different hosts, to tell paths within the site
+    // (e.g. `/foo/bar`, `foo.pdf`) from urls pointing to other hosts (e.g. `//example.com/foo`),
+    // which are returned as https urls
+    pub fn other_host_url(url: &str) -> Option<Url> {
+        let resolve = |base: &str| Url::parse(base).and_then(|base| base.join(url));
+        match (resolve("https://a.invalid/"), resolve("https://b.invalid/")) {
+            (Ok(a), Ok(b)) if a.host() == b.host() => Some(a),
+            _ => None,
+        }
+    }
+
+    fn check_relative_url(&self, url: &str) -> bool {
+        match Self::other_host_url(url) {
+            Some(url) => url
+                .domain()
+                .is_some_and(|domain| self.check_internal_domain(domain)),
+            // paths within the site
+            None => Url::parse("https://a.invalid/")
+                .and_then(|base| base.join(url))
+                .is_ok(),
+        }
+    }
+
+    fn check_internal_domain(&self, domain: &str) -> bool {
+        let domain = domain.to_ascii_lowercase();
+        self.domains.iter().any(|pattern| pattern.matches(&domain))
+            && !self
+                .exclusions
+                .iter()
+                .any(|pattern| pattern.matches(&domain))
+    }
+}
+
+impl DomainPattern {
+    fn new(pattern: &str) -> Option<Self> {
+        if !pattern.contains('*') {
+            return Self::domain(pattern).map(Self::Name);
+        }
+
+        // validated as a domain name, with `*` replaced (`*` alone is not allowed)
+        if !Self::domain(&pattern.replace('*', "x"))?.contains('.') {
+            return None;
+        }
+        let pattern = pattern.split("://").last().unwrap_or(pattern);
+        let pattern = pattern.split('/').next().unwrap_or(pattern);
+        // wildcards in the top level (e.g. `example.*`) would match any sites
+        if pattern.rsplit('.').next().unwrap_or(pattern).contains('*') {
+            return None;
+        }
+        let regex = regex::escape(&pattern.to_ascii_lowercase()).replace(r"\*", "[^.]+");
+        Regex::new(&format!("^{}$", regex)).ok().map(Self::Wildcard)
+    }
+
+    // accepts both `https://example.com` and `example.com`
+    fn domain(domain_name: &str) -> Option<String> {
+        [domain_name.to_string(), format!("https://{}", domain_name)]
+            .iter()
+            .filter_map(|url| Url::parse(url).ok())
+            .find_map(|url| url.domain().map(str::to_string))
+    }
+
+    fn matches(&self, domain: &str) -> bool {
+        match self {
+            // for `example.com`, `foo.example.com` is also considered as an internal link
+            Self::Name(name) => domain == name || domain.ends_with(format!(".{}", name).as_str()),
+            Self::Wildcard(regex) => regex.is_match(domain),
         }
-        false
     }
 }
 
 #[test]
 fn test_get_option() {
     let raw_options = HashMap::from([
-        ("some_random_option".to_string(), "true".to_string()),
-        ("another_option".to_string(), "some-string".to_string()),
+        ("heading_anchors".to_string(), OptionValue::from(true)),
+        (
+            "heading_anchor_prefix".to_string(),
+            OptionValue::from("some-string"),
+        ),
     ]);
-    let options = MarkdonwItOptions::new(raw_options);
+    let options = MarkdonwItOptions::new(raw_options).unwrap();
 
     assert_eq!(
-        options.get_option("some_random_option"),
-        Some(&"true".to_string())
+        options.get_option("heading_anchors"),
+        Some(&OptionValue::Bool(true))
     );
     assert_eq!(
-        options.get_option("another_option"),
-        Some(&"some-string".to_string())
+        options.get_option("heading_anchor_prefix"),
+        Some(&OptionValue::String("some-string".to_string()))
     );
+    assert_eq!(options.get_option("toc_class"), None);
 }
 
 #[test]
 fn test_get_option_or_default() {
-    let raw_options = HashMap::from([("some_random_option".to_string(), "true".to_string())]);
-    let options = MarkdonwItOptions::new(raw_options);
+    let raw_options = HashMap::from([
+        ("toc_class".to_string(), OptionValue::from("toc")),
+        ("toc_max_level".to_string(), OptionValue::from(3)),
+        (
+            "html_allowed_tags".to_string(),
+            OptionValue::from("span, div"),
+        ),
+    ]);
+    let options = MarkdonwItOptions::new(raw_options).unwrap();
 
     assert_eq!(
-        options.get_option_or_default("some_random_option", "false"),
-        "true".to_string()
+        options.get_option_or_default("toc_class", "table-of-contents"),
+        "toc".to_string()
     );
     assert_eq!(
-        options.get_option_or_default("another_option", "default value"),
+        options.get_option_or_default("heading_anchor_class", "default value"),
         "default value".to_string()
     );
+    assert_eq!(options.get_integer_or_default("toc_max_level", 6), 3);
+    assert_eq!(options.get_integer_or_default("toc_min_level", 1), 1);
+
+    // comma separated strings are accepted as lists
+    assert_eq!(
+        options.get_list_or_default("html_allowed_tags", &["p"]),
+        vec!["span".to_string(), "div".to_string()]
+    );
+    assert_eq!(
+        options.get_list_or_default("html_allowed_attributes", &["class"]),
+        vec!["class".to_string()]
+    );
 }
 
 #[test]
 fn test_is_enabled() {
     {
-        // with values set to "true"
-        let raw_options = HashMap::from([("some_option".to_string(), "true".to_string())]);
-        let options = MarkdonwItOptions::new(raw_options);
-        assert_eq!(options.is_enabled("some_option", false), true);
-        assert_eq!(options.is_enabled("some_option", true), true);
+        // with values set to true
+        let raw_options = HashMap::from([("heading_anchors".to_string(), OptionValue::from(true))]);
+        let options = MarkdonwItOptions::new(raw_options).unwrap();
+        assert!(options.is_enabled("heading_anchors", false));
+        assert!(options.is_enabled("heading_anchors", true));
     }
 
     {
-        // with values set to "false"
-        let raw_options = HashMap::from([("some_option".to_string(), "false".to_string())]);
-        let options = MarkdonwItOptions::new(raw_options);
-        assert_eq!(options.is_enabled("some_option", false), false);
-        assert_eq!(options.is_enabled("some_option", true), false);
+        // with values set to false
+        let raw_options =
+            HashMap::from([("table_class_name".to_string(), OptionValue::from(false))]);
+        let options = MarkdonwItOptions::new(raw_options).unwrap();
+        assert!(!options.is_enabled("table_class_name", false));
+        assert!(!options.is_enabled("table_class_name", true));
     }
 
     {
-        // with values set to other than "true" or "false"
+        // with values set to other than true or false
         let raw_options =
-            HashMap::from([("some_option".to_string(), "some_random-value".to_string())]);
-        let options = MarkdonwItOptions::new(raw_options);
-        assert_eq!(options.is_enabled("some_option", false), false);
-        assert_eq!(options.is_enabled("some_option", true), true);
+            HashMap::from([("table_class_name".to_string(), OptionValue::from("table"))]);
+        let options = MarkdonwItOptions::new(raw_options).unwrap();
+        assert!(!options.is_enabled("table_class_name", false));
+        assert!(options.is_enabled("table_class_name", true));
     }
 
     {
         // with no value set
-        let options = MarkdonwItOptions::new(HashMap::new());
-        assert_eq!(options.is_enabled("some_option", false), false);
-        assert_eq!(options.is_enabled("some_option", true), true);
+        let options = MarkdonwItOptions::new(HashMap::new()).unwrap();
+        assert!(!options.is_enabled("heading_anchors", false));
+        assert!(options.is_enabled("heading_anchors", true));
     }
 }
 
+#[test]
+fn test_option_validation() {
+    let validate = |options: Vec<(&str, OptionValue)>| {
+        MarkdonwItOptions::new(
+            options
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value))
+                .collect(),
+        )
+        .map(|_| ())
+        .map_err(|error| error.to_string())
+    };
+
+    assert_eq!(
+        validate(vec![
+            (
+                "internal_domain_name",
+                OptionValue::from("https://example.com")
+            ),
+            ("heading_level_offset", OptionValue::from(2)),
+            ("table_class_name", OptionValue::from(false)),
+            ("html", OptionValue::from("sanitize")),
+            (
+                "allowed_url_schemes",
+                OptionValue::List(vec![OptionValue::from("https")])
+            ),
+        ]),
+        Ok(())
+    );
+
+    // strings from YAML or ENV
+    let options = MarkdonwItOptions::new(HashMap::from([
+        ("heading_level_offset".to_string(), OptionValue::from(" 2")),
+        ("heading_anchors".to_string(), OptionValue::from("true")),
+        ("table_class_name".to_string(), OptionValue::from("false")),
+    ]))
+    .unwrap();
+    assert_eq!(
+        options.get_option("heading_level_offset"),
+        Some(&OptionValue::from(2))
+    );
+    assert_eq!(
+        options.get_option("heading_anchors"),
+        Some(&OptionValue::from(true))
+    );
+    assert!(!options.is_enabled("table_class_name", true));
+
+    // typos
+    assert_eq!(
+        validate(vec![("heading_levl_offset", OptionValue::from(2))]),
+        Err("unknown option: heading_levl_offset (did you mean heading_level_offset?)".to_string())
+    );
+    assert_eq!(
+        validate(vec![("foo", OptionValue::from(2))]),
+        Err("unknown option: foo".to_string())
+    );
+
+    // bad values
+    assert_eq!(
+        validate(vec![("heading_level_offset", OptionValue::from("two"))]),
+        Err("invalid value for heading_level_offset: expected an integer between 0 and 6 or false, got \"two\"".to_string())
+    );
+    assert_eq!(
+        validate(vec![("heading_level_offset", OptionValue::from(7))]),
+        Err("invalid value for heading_level_offset: expected an integer between 0 and 6 or false, got 7".to_string())
+    );
+    assert_eq!(
+        validate(vec![("heading_anchors", OptionValue::from("yes"))]),
+        Err("invalid value for heading_anchors: expected true or false, got \"yes\"".to_string())
+    );
+    assert_eq!(
+        validate(vec![("html", OptionValue::from("strip"))]),
+        Err("invalid value for html: expected one of \"allow\", \"escape\", \"sanitize\" or false, got \"strip\"".to_string())
+    );
+    assert_eq!(
+        validate(vec![(
+            "allowed_url_schemes",
+            OptionValue::List(vec![OptionValue::from(1)])
+        )]),
+        Err(
+            "invalid value for allowed_url_schemes: expected an array of strings, got [1]"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        validate(vec![(
+            "internal_domain_name",
+            OptionValue::from("not a domain")
+        )]),
+        Err(
+            "invalid value for internal_domain_name: \"not a domain\" is not a valid domain name"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        validate(vec![(
+            "internal_domain_exclusions",
+            OptionValue::List(vec![
+                OptionValue::from("*.example.com"),
+                OptionValue::from("*")
+            ])
+        )]),
+        Err(
+            "invalid value for internal_domain_exclusions: \"*\" is not a valid domain name"
+                .to_string()
+        )
+    );
+    assert_eq!(
+        validate(vec![(
+            "image_srcset_widths",
+            OptionValue::from("320,large")
+        )]),
+        Err(
+            "invalid value for image_srcset_widths: expected an array of integers of 1 or more, got \"320,large\""
+                .to_string()
+        )
+    );
+    assert_eq!(
+        validate(vec![(
+            "image_proxy_url",
+            OptionValue::from("https://img.example.jp/proxy?url={url}&sig={hmac}")
+        )]),
+        Err("image_proxy_key is required to sign image_proxy_url".to_string())
+    );
+    assert_eq!(
+        validate(vec![("base_url", OptionValue::from("/foo/bar"))]),
+        Err("invalid value for base_url: \"/foo/bar\" is not an absolute url".to_string())
+    );
+    assert_eq!(
+        validate(vec![(
+            "file_sizes",
+            OptionValue::Map(BTreeMap::from([
+                ("/a.pdf".to_string(), OptionValue::from(100)),
+                ("/b.pdf".to_string(), OptionValue::from(-1)),
+            ]))
+        )]),
+        Err("invalid value for file_sizes: expected a hash of strings to an integer of 0 or more, got {\"/a.pdf\" => 100, \"/b.pdf\" => -1}".to_string())
+    );
+    assert_eq!(
+        validate(vec![
+            ("heading_min_level", OptionValue::from(4)),
+            ("heading_max_level", OptionValue::from(3)),
+        ]),
+        Err("heading_min_level (4) must not be greater than heading_max_level (3)".to_string())
+    );
+
+    // all the errors are reported at once
+    assert_eq!(
+        validate(vec![
+            ("toc_class", OptionValue::from(true)),
+            ("heading_anchors", OptionValue::from(1)),
+        ]),
+        Err("invalid value for heading_anchors: expected true or false, got 1\ninvalid value for toc_class: expected a string, got true".to_string())
+    );
+}
+
 #[test]
 fn test_internal_domain_matches() {
     {
@@ -296,3 +1124,97 @@ fn test_internal_domain_matches() {
         }
     }
 }
+
+#[test]
+fn test_preset() {
+    let options = MarkdonwItOptions::new(HashMap::from([
+        ("preset".to_string(), OptionValue::from("comment")),
+        ("images".to_string(), OptionValue::from(true)),
+    ]))
+    .unwrap();
+
+    // values implied by the preset
+    assert!(!options.is_enabled("tables", true));
+    assert!(!options.is_enabled("html", true));
+    // options given explicitly take precedence
+    assert!(options.is_enabled("images", false));
+    // other options are left as default
+    assert!(options.is_enabled("linkify", true));
+    assert_eq!(options.get_option("internal_domain_name"), None);
+
+    // the default preset does not change anything
+    let options = MarkdonwItOptions::new(HashMap::from([(
+        "preset".to_string(),
+        OptionValue::from("cms"),
+    )]))
+    .unwrap();
+    assert_eq!(options.get_option("tables"), None);
+}
+
+#[test]
+fn test_internal_domain_patterns() {
+    let domain_names =
+        |names: &[&str]| -> Vec<String> { names.iter().map(|name| name.to_string()).collect() };
+
+    {
+        // multiple domains
+        let internal_domain = InternalDomain::with_exclusions(
+            &domain_names(&["city.fuji.shizuoka.jp", "https://fuji-kanko.jp"]),
+            &[],
+        )
+        .unwrap();
+        assert!(internal_domain.matches("https://www.city.fuji.shizuoka.jp/foo"));
+        assert!(internal_domain.matches("https://fuji-kanko.jp"));
+        assert!(internal_domain.matches("http://www.fuji-kanko.jp/foo"));
+        assert!(!internal_domain.matches("https://shizuoka.jp"));
+        assert!(!internal_domain.matches("https://fuji-kanko.jp.example.com"));
+    }
+
+    {
+        // wildcards
+        let internal_domain =
+            InternalDomain::with_exclusions(&domain_names(&["*.shizuoka.lg.jp", "fuji-*.jp"]), &[])
+                .unwrap();
+        assert!(internal_domain.matches("https://pref.shizuoka.lg.jp"));
+        assert!(internal_domain.matches("https://fuji-kanko.jp/foo"));
+        assert!(internal_domain.matches("https://FUJI-city.jp"));
+        // `*` does not match empty
+        assert!(!internal_domain.matches("https://shizuoka.lg.jp"));
+        assert!(!internal_domain.matches("https://kanko-fuji.jp"));
+        // `*` matches one label only
+        assert!(!internal_domain.matches("https://www.pref.shizuoka.lg.jp"));
+        assert!(!internal_domain.matches("https://fuji-kanko.evil.jp"));
+    }
+
+    {
+        // exclusions
+        let internal_domain = InternalDomain::with_exclusions(
+            &domain_names(&["city.fuji.shizuoka.jp"]),
+            &domain_names(&[
+                "forms.city.fuji.shizuoka.jp",
+                "*.partner.city.fuji.shizuoka.jp",
+            ]),
+        )
+        .unwrap();
+        assert!(internal_domain.matches("https://www.city.fuji.shizuoka.jp"));
+        assert!(!internal_domain.matches("https://forms.city.fuji.shizuoka.jp/apply"));
+        assert!(!internal_domain.matches("https://a.forms.city.fuji.shizuoka.jp"));
+        assert!(!internal_domain.matches("https://shop.partner.city.fuji.shizuoka.jp"));
+        assert!(internal_domain.matches("https://partner.city.fuji.shizuoka.jp"));
+
+        // relative paths are always internal, but not urls with other hosts
+        assert!(internal_domain.matches("/foo/bar"));
+        assert!(internal_domain.matches("foo.pdf"));
+        assert!(internal_domain.matches("//www.city.fuji.shizuoka.jp/foo"));
+        assert!(!internal_domain.matches("//forms.city.fuji.shizuoka.jp/foo"));
+        assert!(!internal_domain.matches("//example.com/foo"));
+    }
+
+    // invalid patterns
+    assert!(DomainPattern::new("not a domain").is_none());
+    assert!(DomainPattern::new("*.not a domain").is_none());
+    assert!(DomainPattern::new("*").is_none());
+    // `example.*` would match `example.evil.com`
+    assert!(DomainPattern::new("example.*").is_none());
+    assert!(DomainPattern::new("https://example.*/foo").is_none());
+}
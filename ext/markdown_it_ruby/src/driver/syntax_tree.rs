@@ -0,0 +1,215 @@
+use crate::extensions::heading_level_modification::PlainTextElement;
+use crate::extensions::image_figure::ImageFigure;
+use crate::extensions::link_with_target::{DisallowedLink, LinkWithTarget};
+use crate::extensions::table_decoration::TableWrapper;
+use markdown_it::common::sourcemap::SourceWithLineStarts;
+use markdown_it::parser::core::Root;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::block::code::CodeBlock;
+use markdown_it::plugins::cmark::block::fence::CodeFence;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::list::OrderedList;
+use markdown_it::plugins::cmark::inline::newline::Softbreak;
+use markdown_it::plugins::cmark::inline::{autolink::Autolink, image::Image, link::Link};
+use markdown_it::plugins::extra::linkify::Linkified;
+use markdown_it::plugins::html::{html_block::HtmlBlock, html_inline::HtmlInline};
+use markdown_it::Node;
+use std::sync::Arc;
+
+// an owned snapshot of the parsed AST
+//
+// markdown_it::Node is not Send, so it cannot be held by Ruby objects directly.
+// the tree is copied once after parsing, and subtrees are shared via Arc so that
+// each Ruby node object can keep its own subtree alive.
+#[derive(Debug)]
+pub struct SyntaxNode {
+    pub node_type: &'static str,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Arc<SyntaxNode>>,
+    pub source_position: Option<SourcePosition>,
+    pub text: String,
+}
+
+// 1-based line and column numbers (same as `data-sourcepos` of markdown-it)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SourcePosition {
+    pub start_line: u32,
+    pub start_column: u32,
+    pub end_line: u32,
+    pub end_column: u32,
+}
+
+impl SyntaxNode {
+    pub fn new(root: &Node) -> Self {
+        let source = match root.cast::<Root>() {
+            Some(root) => root.content.as_str(),
+            None => "",
+        };
+        let mapping = SourceWithLineStarts::new(source);
+
+        Self::build(root, &mapping)
+    }
+
+    fn build(node: &Node, mapping: &SourceWithLineStarts) -> Self {
+        let children: Vec<Arc<SyntaxNode>> = node
+            .children
+            .iter()
+            .map(|child| Arc::new(Self::build(child, mapping)))
+            .collect();
+
+        let source_position = node.srcmap.map(|srcmap| {
+            let ((start_line, start_column), (end_line, end_column)) =
+                srcmap.get_positions(mapping);
+            SourcePosition {
+                start_line,
+                start_column,
+                end_line,
+                end_column,
+            }
+        });
+
+        Self {
+            // e.g. `markdown_it::plugins::cmark::block::heading::ATXHeading` => `ATXHeading`
+            node_type: node.name().rsplit("::").next().unwrap_or_default(),
+            attributes: Self::attributes(node),
+            text: Self::text(node, &children),
+            children,
+            source_position,
+        }
+    }
+
+    // type specific properties (url, level, etc.) followed by html attributes
+    fn attributes(node: &Node) -> Vec<(String, String)> {
+        let mut attributes: Vec<(&str, String)> = Vec::new();
+
+        if let Some(heading) = node.cast::<ATXHeading>() {
+            attributes.push(("level", heading.level.to_string()));
+        } else if let Some(heading) = node.cast::<SetextHeader>() {
+            attributes.push(("level", heading.level.to_string()));
+        } else if let Some(link) = node.cast::<LinkWithTarget>() {
+            attributes.push(("href", link.url.clone()));
+            if let Some(title) = link.title.as_ref() {
+                attributes.push(("title", title.clone()));
+            }
+            if let Some(target) = link.target.as_ref() {
+                attributes.push(("target", target.clone()));
+            }
+            if let Some(rel) = link.rel.as_ref() {
+                attributes.push(("rel", rel.clone()));
+            }
+            if let Some(file) = link.file.as_ref() {
+                attributes.push(("file_type", file.file_type.clone()));
+                if let Some(size) = file.size {
+                    attributes.push(("file_size", size.to_string()));
+                }
+            }
+        } else if let Some(link) = node.cast::<DisallowedLink>() {
+            attributes.push(("href", link.url.clone()));
+        } else if let Some(link) = node.cast::<Link>() {
+            attributes.push(("href", link.url.clone()));
+            if let Some(title) = link.title.as_ref() {
+                attributes.push(("title", title.clone()));
+            }
+        } else if let Some(autolink) = node.cast::<Autolink>() {
+            attributes.push(("href", autolink.url.clone()));
+        } else if let Some(linkified) = node.cast::<Linkified>() {
+            attributes.push(("href", linkified.url.clone()));
+        } else if let Some(image) = node.cast::<Image>() {
+            attributes.push(("src", image.url.clone()));
+            attributes.push(("alt", node.collect_text()));
+            if let Some(title) = image.title.as_ref() {
+                attributes.push(("title", title.clone()));
+            }
+        } else if let Some(figure) = node.cast::<ImageFigure>() {
+            if let Some(caption) = figure.caption.as_ref() {
+                attributes.push(("caption", caption.clone()));
+            }
+            if let Some(class) = figure.class.as_ref() {
+                attributes.push(("class", class.clone()));
+            }
+        } else if let Some(wrapper) = node.cast::<TableWrapper>() {
+            attributes.push(("class", wrapper.class.clone()));
+        } else if let Some(list) = node.cast::<OrderedList>() {
+            attributes.push(("start", list.start.to_string()));
+        } else if let Some(fence) = node.cast::<CodeFence>() {
+            attributes.push(("info", fence.info.clone()));
+        }
+
+        attributes.extend(node.attrs.iter().map(|(key, value)| (*key, value.clone())));
+        attributes
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+
+    // same as Node::collect_text, except that literal contents (code, html) are also included
+    fn text(node: &Node, children: &[Arc<SyntaxNode>]) -> String {
+        if let Some(text) = node.cast::<Text>() {
+            text.content.clone()
+        } else if node.is::<Softbreak>() {
+            "\n".to_string()
+        } else if let Some(code) = node.cast::<CodeBlock>() {
+            code.content.clone()
+        } else if let Some(fence) = node.cast::<CodeFence>() {
+            fence.content.clone()
+        } else if let Some(html) = node.cast::<HtmlBlock>() {
+            html.content.clone()
+        } else if let Some(html) = node.cast::<HtmlInline>() {
+            html.content.clone()
+        } else if let Some(element) = node.cast::<PlainTextElement>() {
+            let mut text = element.text.clone();
+            text.extend(children.iter().map(|child| child.text.as_str()));
+            text
+        } else {
+            children.iter().map(|child| child.text.as_str()).collect()
+        }
+    }
+}
+
+#[test]
+fn test_syntax_tree() {
+    let mut md = markdown_it::MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+
+    let root = md.parse("# heading\n\nsome [link](https://example.com \"title\")\ntext");
+    let tree = SyntaxNode::new(&root);
+
+    assert_eq!(tree.node_type, "Root");
+    assert_eq!(tree.children.len(), 2);
+
+    let heading = &tree.children[0];
+    assert_eq!(heading.node_type, "ATXHeading");
+    assert_eq!(
+        heading.attributes,
+        vec![("level".to_string(), "1".to_string())]
+    );
+    assert_eq!(heading.text, "heading");
+    assert_eq!(
+        heading.source_position,
+        Some(SourcePosition {
+            start_line: 1,
+            start_column: 1,
+            end_line: 1,
+            end_column: 9,
+        })
+    );
+
+    let paragraph = &tree.children[1];
+    assert_eq!(paragraph.node_type, "Paragraph");
+    assert_eq!(paragraph.text, "some link\ntext");
+    assert_eq!(paragraph.source_position.unwrap().start_line, 3);
+    assert_eq!(paragraph.source_position.unwrap().end_line, 4);
+
+    let link = &paragraph.children[1];
+    assert_eq!(link.node_type, "Link");
+    assert_eq!(
+        link.attributes,
+        vec![
+            ("href".to_string(), "https://example.com".to_string()),
+            ("title".to_string(), "title".to_string()),
+        ]
+    );
+    assert_eq!(link.text, "link");
+    assert_eq!(link.children[0].node_type, "Text");
+}
@@ -1,18 +1,81 @@
+pub(super) mod base_url;
+pub(super) mod diagnostics;
+pub(super) mod file_link;
+pub(super) mod heading_anchor;
 pub(super) mod heading_level_modification;
+pub(super) mod html_sanitization;
+pub(super) mod image_alt_text;
+pub(super) mod image_attributes;
+pub(super) mod image_figure;
+pub(super) mod image_proxy;
 pub(super) mod link_with_target;
+pub(super) mod scheme_less_url;
 pub(super) mod table_decoration;
+pub(super) mod table_of_contents;
 
 use crate::driver::MarkdonwItOptions;
 use markdown_it::MarkdownIt;
 
+// classes are added to those already given (e.g. by other plugins), not to overwrite them
+fn add_class(attrs: &mut Vec<(&'static str, String)>, class: &str) {
+    match attrs.iter_mut().find(|(key, _)| *key == "class") {
+        Some((_, value)) => {
+            value.push(' ');
+            value.push_str(class);
+        }
+        None => attrs.push(("class", class.to_string())),
+    }
+}
+
 pub(super) fn add(md: &mut MarkdownIt, option: &MarkdonwItOptions) {
-    if option.is_enabled("heading_level_offset", true) {
+    if option.is_enabled("heading_level_offset", true)
+        || heading_level_modification::OPTIONS
+            .iter()
+            .any(|key| option.get_option(key).is_some())
+    {
         heading_level_modification::add(md);
     }
+    // disallowed url schemes are checked with or without target and rel of links
     if option.is_enabled("internal_domain_name", true) {
         link_with_target::add(md);
+    } else {
+        link_with_target::add_url_scheme_check(md);
     }
-    if option.is_enabled("table_class_name", true) {
+    if option.is_enabled("table_class_name", true)
+        || table_decoration::OPTIONS
+            .iter()
+            .any(|key| option.get_option(key).is_some())
+    {
         table_decoration::add(md);
     }
+    // the table of contents links to headings by their ids
+    let table_of_contents = option.is_enabled("table_of_contents", false);
+    if table_of_contents || option.is_enabled("heading_anchors", false) {
+        heading_anchor::add(md);
+    }
+    if table_of_contents {
+        table_of_contents::add(md);
+    }
+    if option.get_option_or_default("missing_alt_text", "allow") != "allow" {
+        image_alt_text::add(md);
+    }
+    if option.is_enabled("image_figures", true) && option.get_option("image_figures").is_some() {
+        image_figure::add(md);
+    }
+    if image_attributes::OPTIONS
+        .iter()
+        .any(|key| option.get_option(key).is_some())
+    {
+        image_attributes::add(md);
+    }
+    // after image attributes, which are looked up by the original urls (and whose srcset is proxied)
+    if option.get_option("image_proxy_url").is_some() {
+        image_proxy::add(md);
+    }
+    if option.get_option("base_url").is_some() || option.get_option("asset_base_url").is_some() {
+        base_url::add(md);
+    }
+    if option.get_option_or_default("html", "allow") == "sanitize" {
+        html_sanitization::add(md);
+    }
 }
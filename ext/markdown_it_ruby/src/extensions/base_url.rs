@@ -0,0 +1,135 @@
+// resolves relative urls of links and images into absolute ones (e.g. for RSS feeds and emails),
+// where `base_url` is the url of the page and `asset_base_url` is that of images, if different
+//
+// e.g. with `base_url: "https://example.com/news/"`,
+// `[foo](/foo/bar/1234)` => `<a href="https://example.com/foo/bar/1234">foo</a>`
+use markdown_it::parser::core::CoreRule;
+use markdown_it::plugins::cmark::inline::{image::Image, link::Link};
+use markdown_it::{MarkdownIt, Node};
+use url::{ParseError, Url};
+
+use crate::driver::MarkdonwItOptions;
+use crate::extensions::link_with_target::LinkWithTarget;
+
+struct BaseUrlRule;
+
+impl CoreRule for BaseUrlRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(options) = md.ext.get::<MarkdonwItOptions>() else {
+            return;
+        };
+        let base_url = parse_option(options, "base_url");
+        let asset_base_url = asset_base_url(options);
+
+        root.walk_mut(|node, _| {
+            if let (Some(base_url), Some(link)) = (&base_url, node.cast_mut::<LinkWithTarget>()) {
+                link.url = resolve(base_url, &link.url);
+            } else if let (Some(base_url), Some(link)) = (&base_url, node.cast_mut::<Link>()) {
+                link.url = resolve(base_url, &link.url);
+            } else if let (Some(asset_base_url), Some(image)) =
+                (&asset_base_url, node.cast_mut::<Image>())
+            {
+                image.url = resolve(asset_base_url, &image.url);
+            }
+        });
+    }
+}
+
+// both are validated as absolute urls
+fn parse_option(options: &MarkdonwItOptions, key: &str) -> Option<Url> {
+    options
+        .get_string(key)
+        .and_then(|url| Url::parse(&url).ok())
+}
+
+// the base url of images, which falls back to `base_url`
+pub fn asset_base_url(options: &MarkdonwItOptions) -> Option<Url> {
+    parse_option(options, "asset_base_url").or_else(|| parse_option(options, "base_url"))
+}
+
+// urls with schemes and empty ones (i.e. disallowed links) are kept as they are
+pub fn resolve(base_url: &Url, url: &str) -> String {
+    if url.is_empty() || !matches!(Url::parse(url), Err(ParseError::RelativeUrlWithoutBase)) {
+        return url.to_string();
+    }
+    match base_url.join(url) {
+        Ok(resolved) => resolved.to_string(),
+        Err(_) => url.to_string(),
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    // after links are classified into internal and external ones by their original urls
+    md.add_rule::<BaseUrlRule>().after_all();
+}
+
+#[test]
+fn test_resolve() {
+    let base_url = Url::parse("https://example.com/news/").unwrap();
+
+    assert_eq!(
+        resolve(&base_url, "/foo/bar/1234"),
+        "https://example.com/foo/bar/1234"
+    );
+    assert_eq!(
+        resolve(&base_url, "2024/05.html?page=2#top"),
+        "https://example.com/news/2024/05.html?page=2#top"
+    );
+    assert_eq!(
+        resolve(&base_url, "../hoge.pdf"),
+        "https://example.com/hoge.pdf"
+    );
+    assert_eq!(
+        resolve(&base_url, "//cdn.example.com/a.png"),
+        "https://cdn.example.com/a.png"
+    );
+    assert_eq!(resolve(&base_url, "#top"), "https://example.com/news/#top");
+
+    assert_eq!(
+        resolve(&base_url, "https://example.org/foo"),
+        "https://example.org/foo"
+    );
+    assert_eq!(
+        resolve(&base_url, "mailto:foo@example.com"),
+        "mailto:foo@example.com"
+    );
+    assert_eq!(resolve(&base_url, ""), "");
+}
+
+#[test]
+fn test_base_url() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let render = |options: Vec<(&str, &str)>| {
+        let mut md = MarkdownIt::new();
+        markdown_it::plugins::cmark::add(&mut md);
+        add(&mut md);
+        MarkdonwItOptions::new(
+            options
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), OptionValue::from(value)))
+                .collect::<HashMap<_, _>>(),
+        )
+        .unwrap()
+        .add(&mut md);
+        md.parse("[foo](/foo/bar/1234) ![bar](images/bar.png) [baz](https://example.org)")
+            .render()
+    };
+
+    assert_eq!(
+        render(vec![("base_url", "https://example.com/news/")]),
+        "<p><a href=\"https://example.com/foo/bar/1234\">foo</a> <img src=\"https://example.com/news/images/bar.png\" alt=\"bar\"> <a href=\"https://example.org\">baz</a></p>\n"
+    );
+    assert_eq!(
+        render(vec![
+            ("base_url", "https://example.com/news/"),
+            ("asset_base_url", "https://cdn.example.com/"),
+        ]),
+        "<p><a href=\"https://example.com/foo/bar/1234\">foo</a> <img src=\"https://cdn.example.com/images/bar.png\" alt=\"bar\"> <a href=\"https://example.org\">baz</a></p>\n"
+    );
+    assert_eq!(
+        render(vec![("asset_base_url", "https://cdn.example.com/")]),
+        "<p><a href=\"/foo/bar/1234\">foo</a> <img src=\"https://cdn.example.com/images/bar.png\" alt=\"bar\"> <a href=\"https://example.org\">baz</a></p>\n"
+    );
+}
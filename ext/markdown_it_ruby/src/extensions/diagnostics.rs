@@ -0,0 +1,65 @@
+use markdown_it::common::sourcemap::{SourcePos, SourceWithLineStarts};
+use markdown_it::parser::core::Root;
+use markdown_it::parser::extset::NodeExt;
+use markdown_it::Node;
+
+// problems found by extensions while processing a document (stored in `root.ext`)
+// the driver refuses to convert documents with errors, and returns warnings along with outputs
+#[derive(Debug, Default)]
+pub struct Diagnostics {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+impl NodeExt for Diagnostics {}
+
+impl Diagnostics {
+    pub fn add_errors(root: &mut Node, errors: Vec<String>) {
+        if errors.is_empty() {
+            return;
+        }
+        root.ext
+            .get_or_insert_default::<Self>()
+            .errors
+            .extend(errors);
+    }
+
+    pub fn add_warnings(root: &mut Node, warnings: Vec<String>) {
+        if warnings.is_empty() {
+            return;
+        }
+        root.ext
+            .get_or_insert_default::<Self>()
+            .warnings
+            .extend(warnings);
+    }
+}
+
+// resolves line numbers (1-based) of nodes, to point editors to the source
+pub struct SourceLines {
+    mapping: SourceWithLineStarts,
+}
+
+impl SourceLines {
+    pub fn new(root: &Node) -> Self {
+        let source = match root.cast::<Root>() {
+            Some(root) => root.content.as_str(),
+            None => "",
+        };
+        Self {
+            mapping: SourceWithLineStarts::new(source),
+        }
+    }
+
+    pub fn line(&self, srcmap: Option<SourcePos>) -> Option<u32> {
+        srcmap.map(|srcmap| srcmap.get_positions(&self.mapping).0 .0)
+    }
+
+    // e.g. `line 3: some message`
+    pub fn message(&self, srcmap: Option<SourcePos>, message: &str) -> String {
+        match self.line(srcmap) {
+            Some(line) => format!("line {}: {}", line, message),
+            None => message.to_string(),
+        }
+    }
+}
@@ -0,0 +1,155 @@
+// file types and sizes of links to documents (e.g. `/foo/bar/hoge.pdf`),
+// which accessibility guidelines require to be shown before users download them
+//
+// e.g. `<a href="/foo/bar/hoge.pdf" class="file-link">hoge（PDF：123KB）</a>`
+use markdown_it::Renderer;
+use std::collections::HashMap;
+
+use crate::driver::MarkdonwItOptions;
+
+const DEFAULT_EXTENSIONS: [&str; 5] = ["pdf", "docx", "xlsx", "csv", "zip"];
+
+// a file type and size added to a link by `FileLinkPolicy`
+#[derive(Debug, Clone)]
+pub struct FileAnnotation {
+    // e.g. `PDF`
+    pub file_type: String,
+    pub size: Option<i64>,
+    pub class: String,
+    pub label: String,
+}
+
+impl FileAnnotation {
+    pub fn render(&self, fmt: &mut dyn Renderer) {
+        fmt.text(&self.label);
+    }
+}
+
+#[derive(Debug)]
+pub struct FileLinkPolicy {
+    extensions: Vec<String>,
+    class: String,
+    // `{type}` and `{size}` are replaced with those of the file
+    label: String,
+    label_without_size: String,
+    // bytes by path
+    sizes: HashMap<String, i64>,
+}
+
+impl FileLinkPolicy {
+    // `None` unless `file_link_annotation: true` is given
+    pub fn new(options: Option<&MarkdonwItOptions>) -> Option<Self> {
+        let options =
+            options.filter(|options| options.is_enabled("file_link_annotation", false))?;
+
+        Some(Self {
+            extensions: options
+                .get_list_or_default("file_link_extensions", &DEFAULT_EXTENSIONS)
+                .iter()
+                .map(|extension| extension.trim_start_matches('.').to_ascii_lowercase())
+                .collect(),
+            class: options.get_option_or_default("file_link_class", "file-link"),
+            label: options.get_option_or_default("file_link_label", "（{type}：{size}）"),
+            label_without_size: options
+                .get_option_or_default("file_link_label_without_size", "（{type}）"),
+            sizes: options.get_integer_map("file_sizes"),
+        })
+    }
+
+    pub fn annotation(&self, url: &str) -> Option<FileAnnotation> {
+        // `/foo/bar/hoge.pdf?version=2#page=3` => `/foo/bar/hoge.pdf`
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let file_name = path.rsplit('/').next().unwrap_or_default();
+        let (_, extension) = file_name.rsplit_once('.')?;
+        let extension = extension.to_ascii_lowercase();
+        if !self.extensions.contains(&extension) {
+            return None;
+        }
+
+        let file_type = extension.to_ascii_uppercase();
+        let size = self
+            .sizes
+            .get(url)
+            .or_else(|| self.sizes.get(path))
+            .copied();
+        let label = match size {
+            Some(size) => self.label.replace("{size}", &format_size(size)),
+            None => self.label_without_size.clone(),
+        }
+        .replace("{type}", &file_type);
+
+        Some(FileAnnotation {
+            file_type,
+            size,
+            class: self.class.clone(),
+            label,
+        })
+    }
+}
+
+// e.g. `512B`, `123KB`, `4.5MB`
+fn format_size(bytes: i64) -> String {
+    const KB: i64 = 1024;
+    const MB: i64 = 1024 * KB;
+    const GB: i64 = 1024 * MB;
+
+    if bytes < KB {
+        format!("{}B", bytes)
+    } else if bytes < MB {
+        // rounded up, not to show `0KB`
+        format!("{}KB", (bytes + KB - 1) / KB)
+    } else if bytes < GB {
+        format!("{:.1}MB", bytes as f64 / MB as f64)
+    } else {
+        format!("{:.1}GB", bytes as f64 / GB as f64)
+    }
+}
+
+#[test]
+fn test_file_annotation() {
+    use crate::driver::OptionValue;
+    use std::collections::BTreeMap;
+
+    let options = MarkdonwItOptions::new(HashMap::from([
+        ("file_link_annotation".to_string(), OptionValue::from(true)),
+        (
+            "file_sizes".to_string(),
+            OptionValue::Map(BTreeMap::from([
+                ("/foo/bar/hoge.pdf".to_string(), OptionValue::from(125_952)),
+                ("data.CSV".to_string(), OptionValue::from(300)),
+            ])),
+        ),
+    ]))
+    .unwrap();
+    let policy = FileLinkPolicy::new(Some(&options)).unwrap();
+    let label = |url: &str| policy.annotation(url).map(|annotation| annotation.label);
+
+    assert_eq!(
+        label("/foo/bar/hoge.pdf"),
+        Some("（PDF：123KB）".to_string())
+    );
+    assert_eq!(
+        label("/foo/bar/hoge.pdf?version=2#page=3"),
+        Some("（PDF：123KB）".to_string())
+    );
+    assert_eq!(label("data.CSV"), Some("（CSV：300B）".to_string()));
+    assert_eq!(
+        label("https://example.com/report.xlsx"),
+        Some("（XLSX）".to_string())
+    );
+    assert_eq!(label("/foo/bar/index.html"), None);
+    assert_eq!(label("/foo.pdf/bar"), None);
+    assert_eq!(label("https://example.com"), None);
+
+    // disabled by default
+    assert!(FileLinkPolicy::new(Some(&MarkdonwItOptions::default())).is_none());
+}
+
+#[test]
+fn test_format_size() {
+    assert_eq!(format_size(0), "0B");
+    assert_eq!(format_size(1023), "1023B");
+    assert_eq!(format_size(1025), "2KB");
+    assert_eq!(format_size(4_718_592), "4.5MB");
+    assert_eq!(format_size(3 * 1024 * 1024 * 1024), "3.0GB");
+}
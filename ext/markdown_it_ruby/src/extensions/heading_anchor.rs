@@ -0,0 +1,173 @@
+use crate::driver::MarkdonwItOptions;
+use markdown_it::parser::core::CoreRule;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use std::collections::HashSet;
+
+// a self-link placed at the beginning of a heading (`<a class="anchor" href="#id">`)
+// its contents are left empty, so that the style can be decided by css
+#[derive(Debug)]
+pub struct HeadingAnchor;
+
+impl NodeValue for HeadingAnchor {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        fmt.open("a", &node.attrs);
+        fmt.close("a");
+    }
+}
+
+// generates url fragments from heading texts
+//
+// letters and digits of any language are kept as is (except that they are lowercased),
+// whitespaces are replaced with `-` and other symbols are removed.
+// e.g. `タイトル２-１` => `タイトル２-１`, `Hello, World!` => `hello-world`
+#[derive(Debug, Default)]
+pub struct Slugger {
+    prefix: String,
+    used: HashSet<String>,
+}
+
+impl Slugger {
+    pub fn new(prefix: &str) -> Self {
+        Self {
+            prefix: prefix.to_string(),
+            used: HashSet::new(),
+        }
+    }
+
+    // returns a slug unique within the document, by adding `-1`, `-2`, ... to repeated ones
+    pub fn slug(&mut self, text: &str) -> String {
+        let mut base = format!("{}{}", self.prefix, Self::slugify(text));
+        if base.is_empty() {
+            base = "section".to_string();
+        }
+
+        let mut slug = base.clone();
+        let mut count = 0;
+        while self.used.contains(&slug) {
+            count += 1;
+            slug = format!("{}-{}", base, count);
+        }
+        self.used.insert(slug.clone());
+        slug
+    }
+
+    fn slugify(text: &str) -> String {
+        text.trim()
+            .chars()
+            .filter_map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' {
+                    Some(c)
+                } else if c.is_whitespace() {
+                    Some('-')
+                } else {
+                    None
+                }
+            })
+            .flat_map(char::to_lowercase)
+            .collect()
+    }
+}
+
+pub(super) struct HeadingAnchorRule;
+
+impl CoreRule for HeadingAnchorRule {
+    // a custom function that will be invoked once per document.
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let default_options = MarkdonwItOptions::default();
+        let options = md
+            .ext
+            .get::<MarkdonwItOptions>()
+            .unwrap_or(&default_options);
+        let mut slugger = Slugger::new(&options.get_option_or_default("heading_anchor_prefix", ""));
+        let anchor_link = options.is_enabled("heading_anchor_link", false);
+        let anchor_class = options.get_option_or_default("heading_anchor_class", "anchor");
+
+        root.walk_mut(|node, _| {
+            if !node.is::<ATXHeading>() && !node.is::<SetextHeader>() {
+                return;
+            }
+            // ids given by other rules are kept as is
+            if node.attrs.iter().any(|(key, _)| *key == "id") {
+                return;
+            }
+
+            let id = slugger.slug(&node.collect_text());
+            if anchor_link {
+                let mut anchor = Node::new(HeadingAnchor);
+                anchor.attrs = vec![
+                    ("class", anchor_class.clone()),
+                    ("href", format!("#{}", id)),
+                    ("aria-hidden", "true".to_string()),
+                ];
+                node.children.insert(0, anchor);
+            }
+            node.attrs.push(("id", id));
+        });
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    // headings have to be settled (e.g. level modifications) before ids are given
+    md.add_rule::<HeadingAnchorRule>().after_all();
+}
+
+#[test]
+fn test_slugger() {
+    let mut slugger = Slugger::new("");
+    assert_eq!(slugger.slug("タイトル２-１"), "タイトル２-１");
+    assert_eq!(slugger.slug("Hello, World!"), "hello-world");
+    assert_eq!(slugger.slug("  見出し 1（概要）  "), "見出し-1概要");
+    assert_eq!(slugger.slug("!!!"), "section");
+
+    // repeated slugs are numbered
+    assert_eq!(slugger.slug("タイトル２-１"), "タイトル２-１-1");
+    assert_eq!(slugger.slug("タイトル２-１"), "タイトル２-１-2");
+    assert_eq!(slugger.slug("hello world"), "hello-world-1");
+
+    // with prefix
+    let mut slugger = Slugger::new("section-");
+    assert_eq!(slugger.slug("Title"), "section-title");
+    assert_eq!(slugger.slug("Title"), "section-title-1");
+}
+
+#[test]
+fn test_heading_anchor() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    {
+        // without options
+        let src = "# タイトル１\n## タイトル２-１\n## タイトル２-１\nSetext *heading*\n---";
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<h1 id=\"タイトル１\">タイトル１</h1>\n<h2 id=\"タイトル２-１\">タイトル２-１</h2>\n<h2 id=\"タイトル２-１-1\">タイトル２-１</h2>\n<h2 id=\"setext-heading\">Setext <em>heading</em></h2>\n"
+        );
+    }
+
+    {
+        // with options
+        let options = MarkdonwItOptions::new(HashMap::from([
+            (
+                "heading_anchor_prefix".to_string(),
+                OptionValue::from("toc-"),
+            ),
+            ("heading_anchor_link".to_string(), OptionValue::from(true)),
+        ]))
+        .unwrap();
+        options.add(&mut md);
+
+        let src = "# Heading\n## Heading";
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<h1 id=\"toc-heading\"><a class=\"anchor\" href=\"#toc-heading\" aria-hidden=\"true\"></a>Heading</h1>\n<h2 id=\"toc-heading-1\"><a class=\"anchor\" href=\"#toc-heading-1\" aria-hidden=\"true\"></a>Heading</h2>\n"
+        );
+    }
+}
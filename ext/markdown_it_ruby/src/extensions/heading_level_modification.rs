@@ -1,11 +1,19 @@
 use crate::driver::MarkdonwItOptions;
+use crate::extensions::diagnostics::{Diagnostics, SourceLines};
 use markdown_it::parser::core::CoreRule;
 use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::emphasis::Strong;
 use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
 
+// the extension is added when any of them is given (besides `heading_level_offset`, enabled by default),
+// with the offset treated as 0 when it is disabled
+pub const OPTIONS: [&str; 3] = ["heading_min_level", "heading_max_level", "heading_overflow"];
+
 #[derive(Debug)]
-struct PlainTextElement {
-    text: String,
+pub struct PlainTextElement {
+    pub text: String,
 }
 
 impl NodeValue for PlainTextElement {
@@ -18,37 +26,123 @@ impl NodeValue for PlainTextElement {
     }
 }
 
+// how headings out of the allowed range (`heading_min_level` - `heading_max_level`) are rendered
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum HeadingOverflow {
+    // `<p>#### heading</p>` (default)
+    Literal,
+    // the nearest allowed level
+    Clamp,
+    // `<p>heading</p>`
+    Paragraph,
+    // `<p><strong>heading</strong></p>`
+    Strong,
+    // the conversion fails, reporting the line numbers of such headings
+    Error,
+}
+
+impl HeadingOverflow {
+    fn new(value: &str) -> Self {
+        match value {
+            "clamp" => Self::Clamp,
+            "paragraph" => Self::Paragraph,
+            "strong" => Self::Strong,
+            "error" => Self::Error,
+            _ => Self::Literal,
+        }
+    }
+}
+
 struct HeadingLevelModificationRule;
 
 impl CoreRule for HeadingLevelModificationRule {
     fn run(root: &mut Node, md: &MarkdownIt) {
-        let options = md.ext.get::<MarkdonwItOptions>();
-        let heading_level_offset = match options {
-            Some(options) => options
-                .get_option_or_default("heading_level_offset", "0")
-                .parse::<u8>()
-                .unwrap_or(0),
-            None => 0,
-        };
-        // walk through AST recursively and count the number of two
-        // custom nodes added by other two rules
+        let default_options = MarkdonwItOptions::default();
+        let options = md
+            .ext
+            .get::<MarkdonwItOptions>()
+            .unwrap_or(&default_options);
+        let heading_level_offset = Self::level_option(options, "heading_level_offset", 0);
+        let min_level = Self::level_option(options, "heading_min_level", 1).clamp(1, 6);
+        let max_level = Self::level_option(options, "heading_max_level", 6).clamp(min_level, 6);
+        let overflow =
+            HeadingOverflow::new(&options.get_option_or_default("heading_overflow", "literal"));
+
+        let source_lines = SourceLines::new(root);
+        let mut errors = Vec::new();
+
         root.walk_mut(|node, _| {
-            if let Some(heading) = node.cast::<ATXHeading>() {
-                // do not render level 1 and level > 4 headings (# and ####+)
-                // illegal `headings` has to be treated as plain text
-                let new_heading_level = heading.level + heading_level_offset;
-                // if the new heading level is out of the accepted range (1-6), treat it as plain text
-                if new_heading_level < 1 || new_heading_level > 6 {
+            // the literal prefix is used when headings are rendered as plain text
+            // (the underline of Setext headings, `===` or `---`, is not kept)
+            let (level, literal_prefix) = if let Some(heading) = node.cast::<ATXHeading>() {
+                (heading.level, "#".repeat(heading.level as usize) + " ")
+            } else if let Some(heading) = node.cast::<SetextHeader>() {
+                (heading.level, String::new())
+            } else {
+                return;
+            };
+            let new_heading_level = level.saturating_add(heading_level_offset);
+
+            if (min_level..=max_level).contains(&new_heading_level) {
+                Self::set_level(node, new_heading_level);
+                return;
+            }
+
+            match overflow {
+                HeadingOverflow::Literal => node.replace(PlainTextElement {
+                    text: literal_prefix,
+                }),
+                HeadingOverflow::Clamp => {
+                    Self::set_level(node, new_heading_level.clamp(min_level, max_level))
+                }
+                HeadingOverflow::Paragraph => node.replace(PlainTextElement {
+                    text: String::new(),
+                }),
+                HeadingOverflow::Strong => {
+                    let mut strong = Node::new(Strong { marker: '*' });
+                    strong.srcmap = node.srcmap;
+                    strong.children = std::mem::take(&mut node.children);
+                    node.replace(Paragraph);
+                    node.children.push(strong);
+                }
+                HeadingOverflow::Error => {
+                    errors.push(source_lines.message(
+                        node.srcmap,
+                        &format!(
+                            "heading level {} of \"{}\" is out of the allowed range ({}-{})",
+                            new_heading_level,
+                            node.collect_text(),
+                            min_level,
+                            max_level
+                        ),
+                    ));
                     node.replace(PlainTextElement {
-                        text: "#".repeat(heading.level as usize) + " ",
-                    });
-                } else {
-                    node.replace(ATXHeading {
-                        level: new_heading_level,
+                        text: literal_prefix,
                     });
                 }
             }
         });
+
+        Diagnostics::add_errors(root, errors);
+    }
+}
+
+impl HeadingLevelModificationRule {
+    fn level_option(options: &MarkdonwItOptions, key: &str, default: u8) -> u8 {
+        // validated to be within 0-6
+        options.get_integer_or_default(key, default.into()) as u8
+    }
+
+    fn set_level(node: &mut Node, level: u8) {
+        if let Some(heading) = node.cast::<SetextHeader>() {
+            // SetextHeader only renders h1 and h2
+            if level <= 2 {
+                let marker = heading.marker;
+                node.replace(SetextHeader { level, marker });
+                return;
+            }
+        }
+        node.replace(ATXHeading { level });
     }
 }
 
@@ -58,6 +152,7 @@ pub fn add(md: &mut MarkdownIt) {
 
 #[test]
 fn test_heading_modification() {
+    use crate::driver::OptionValue;
     use std::collections::HashMap;
 
     let mut md = MarkdownIt::new();
@@ -102,8 +197,9 @@ fn test_heading_modification() {
         // with options
         let options = MarkdonwItOptions::new(HashMap::from([(
             "heading_level_offset".to_string(),
-            "3".to_string(),
-        )]));
+            OptionValue::from(3),
+        )]))
+        .unwrap();
         options.add(&mut md);
         {
             // for heading levels out of the accepted range (h4 - shifted to h7)
@@ -137,3 +233,143 @@ fn test_heading_modification() {
             );
     }
 }
+
+#[test]
+fn test_setext_heading_modification() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    {
+        // without options
+        let src = "heading 1\n=========\n\nheading 2\n---------";
+        let html = md.parse(src).render();
+
+        assert_eq!(html, "<h1>heading 1</h1>\n<h2>heading 2</h2>\n");
+    }
+
+    {
+        // with options
+        let options = MarkdonwItOptions::new(HashMap::from([(
+            "heading_level_offset".to_string(),
+            OptionValue::from(2),
+        )]))
+        .unwrap();
+        options.add(&mut md);
+
+        let src = "heading 1\n=========\n\nheading 2\n---------";
+        let html = md.parse(src).render();
+
+        assert_eq!(html, "<h3>heading 1</h3>\n<h4>heading 2</h4>\n");
+    }
+
+    {
+        // for heading levels out of the accepted range (h2 - shifted to h7)
+        let options = MarkdonwItOptions::new(HashMap::from([(
+            "heading_level_offset".to_string(),
+            OptionValue::from(5),
+        )]))
+        .unwrap();
+        options.add(&mut md);
+
+        let src = "heading 1\n=========\n\nheading *2*\n---------";
+        let html = md.parse(src).render();
+
+        assert_eq!(html, "<h6>heading 1</h6>\n<p>heading <em>2</em></p>\n");
+    }
+}
+
+#[test]
+fn test_heading_overflow() {
+    use crate::driver::OptionValue;
+    use crate::extensions::diagnostics::Diagnostics;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    // h2 and the Setext h2 are shifted to h6, which exceeds `heading_max_level`
+    let src = "# heading 1\n## heading *2*\n\nheading 3\n---------";
+    let parse = |md: &mut MarkdownIt, heading_overflow: &str| {
+        let options = MarkdonwItOptions::new(HashMap::from([
+            ("heading_level_offset".to_string(), OptionValue::from(4)),
+            ("heading_max_level".to_string(), OptionValue::from(5)),
+            (
+                "heading_overflow".to_string(),
+                OptionValue::from(heading_overflow),
+            ),
+        ]))
+        .unwrap();
+        options.add(md);
+        md.parse(src)
+    };
+
+    {
+        // clamp
+        let root = parse(&mut md, "clamp");
+        assert_eq!(
+            root.render(),
+            "<h5>heading 1</h5>\n<h5>heading <em>2</em></h5>\n<h5>heading 3</h5>\n"
+        );
+    }
+
+    {
+        // paragraph
+        let root = parse(&mut md, "paragraph");
+        assert_eq!(
+            root.render(),
+            "<h5>heading 1</h5>\n<p>heading <em>2</em></p>\n<p>heading 3</p>\n"
+        );
+    }
+
+    {
+        // strong
+        let root = parse(&mut md, "strong");
+        assert_eq!(
+            root.render(),
+            "<h5>heading 1</h5>\n<p><strong>heading <em>2</em></strong></p>\n<p><strong>heading 3</strong></p>\n"
+        );
+    }
+
+    {
+        // literal
+        let root = parse(&mut md, "literal");
+        assert_eq!(
+            root.render(),
+            "<h5>heading 1</h5>\n<p>## heading <em>2</em></p>\n<p>heading 3</p>\n"
+        );
+        assert!(root.ext.get::<Diagnostics>().is_none());
+    }
+
+    {
+        // error
+        let root = parse(&mut md, "error");
+        assert_eq!(
+            root.ext.get::<Diagnostics>().unwrap().errors,
+            vec![
+                "line 2: heading level 6 of \"heading 2\" is out of the allowed range (1-5)",
+                "line 4: heading level 6 of \"heading 3\" is out of the allowed range (1-5)",
+            ]
+        );
+    }
+
+    {
+        // with heading_min_level (e.g. h1 is reserved for the page title)
+        let options = MarkdonwItOptions::new(HashMap::from([
+            ("heading_min_level".to_string(), OptionValue::from(2)),
+            ("heading_overflow".to_string(), OptionValue::from("clamp")),
+        ]))
+        .unwrap();
+        options.add(&mut md);
+
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<h2>heading 1</h2>\n<h2>heading <em>2</em></h2>\n<h2>heading 3</h2>\n"
+        );
+    }
+}
@@ -0,0 +1,296 @@
+use crate::driver::MarkdonwItOptions;
+use crate::regex;
+use markdown_it::common::utils::{escape_html, get_entity_from_str, is_valid_entity_code};
+use markdown_it::parser::core::CoreRule;
+use markdown_it::plugins::html::{html_block::HtmlBlock, html_inline::HtmlInline};
+use markdown_it::{MarkdownIt, Node};
+use std::collections::HashSet;
+
+// tags and attributes kept by `html: "sanitize"` unless overridden by
+// `html_allowed_tags` / `html_allowed_attributes`
+const DEFAULT_ALLOWED_TAGS: &str = "a,abbr,b,blockquote,br,caption,cite,code,dd,del,details,div,dl,dt,em,figcaption,figure,h1,h2,h3,h4,h5,h6,hr,i,img,ins,kbd,li,mark,ol,p,pre,q,rp,rt,ruby,s,samp,small,span,strong,sub,summary,sup,table,tbody,td,tfoot,th,thead,tr,u,ul,var";
+const DEFAULT_ALLOWED_ATTRIBUTES: &str =
+    "alt,cite,class,colspan,datetime,dir,height,href,id,lang,rowspan,src,title,width";
+
+// attributes holding a url, checked against `DISALLOWED_URL_SCHEMES`
+const URL_ATTRIBUTES: [&str; 3] = ["href", "src", "cite"];
+const DISALLOWED_URL_SCHEMES: [&str; 4] = ["javascript:", "vbscript:", "data:", "file:"];
+
+#[derive(Debug)]
+pub struct HtmlSanitizer {
+    allowed_tags: HashSet<String>,
+    allowed_attributes: HashSet<String>,
+}
+
+impl HtmlSanitizer {
+    pub fn new(options: &MarkdonwItOptions) -> Self {
+        Self {
+            allowed_tags: Self::list(options, "html_allowed_tags", DEFAULT_ALLOWED_TAGS),
+            allowed_attributes: Self::list(
+                options,
+                "html_allowed_attributes",
+                DEFAULT_ALLOWED_ATTRIBUTES,
+            ),
+        }
+    }
+
+    fn list(options: &MarkdonwItOptions, key: &str, default: &str) -> HashSet<String> {
+        let default: Vec<&str> = default.split(',').collect();
+        options
+            .get_list_or_default(key, &default)
+            .iter()
+            .map(|value| value.trim().to_ascii_lowercase())
+            .collect()
+    }
+
+    // keeps allowed tags (with allowed attributes only) and drops everything else.
+    // text between tags is kept as is, except for stray `<` which is escaped.
+    pub fn sanitize(&self, html: &str) -> String {
+        // contents of these elements are never meant to be displayed
+        let html = regex!(r"(?s)<!--.*?(-->|\z)").replace_all(html, "");
+        let html = regex!(r"(?is)<script\b.*?(</script\s*>|\z)").replace_all(&html, "");
+        let html = regex!(r"(?is)<style\b.*?(</style\s*>|\z)").replace_all(&html, "");
+
+        let tag_regex = regex!(
+            r"^<(/?)([a-zA-Z][a-zA-Z0-9-]*)((?:\s+[^\s/>][^\s/>=]*(?:\s*=\s*(?:[^\s\x22'=<>`]+|'[^']*'|\x22[^\x22]*\x22))?)*)\s*(/?)>"
+        );
+        // e.g. `<!DOCTYPE html>`, `<?xml ... ?>`, `<![CDATA[ ... ]]>`
+        let declaration_regex = regex!(r"^<[!?][^>]*>");
+
+        let mut sanitized = String::with_capacity(html.len());
+        let mut rest = html.as_ref();
+        while let Some(start) = rest.find('<') {
+            sanitized.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            if let Some(captures) = tag_regex.captures(rest) {
+                let tag_name = captures[2].to_ascii_lowercase();
+                if self.allowed_tags.contains(&tag_name) {
+                    sanitized.push('<');
+                    sanitized.push_str(&captures[1]);
+                    sanitized.push_str(&tag_name);
+                    if captures[1].is_empty() {
+                        sanitized.push_str(&self.sanitize_attributes(&captures[3]));
+                        if !captures[4].is_empty() {
+                            sanitized.push_str(" /");
+                        }
+                    }
+                    sanitized.push('>');
+                }
+                rest = &rest[captures[0].len()..];
+            } else if let Some(declaration) = declaration_regex.find(rest) {
+                rest = &rest[declaration.end()..];
+            } else {
+                sanitized.push_str("&lt;");
+                rest = &rest[1..];
+            }
+        }
+        sanitized.push_str(rest);
+
+        sanitized
+    }
+
+    fn sanitize_attributes(&self, attributes: &str) -> String {
+        let attribute_regex = regex!(
+            r"([^\s/>][^\s/>=]*)(?:\s*=\s*(?:([^\s\x22'=<>`]+)|'([^']*)'|\x22([^\x22]*)\x22))?"
+        );
+
+        let mut sanitized = String::new();
+        for captures in attribute_regex.captures_iter(attributes) {
+            let name = captures[1].to_ascii_lowercase();
+            // event handlers (onclick, onerror, ...) are never allowed
+            if name.starts_with("on") || !self.allowed_attributes.contains(&name) {
+                continue;
+            }
+
+            let value = captures
+                .get(2)
+                .or_else(|| captures.get(3))
+                .or_else(|| captures.get(4))
+                .map(|value| Self::decode_entities(value.as_str()));
+            match value {
+                None => {
+                    sanitized.push(' ');
+                    sanitized.push_str(&name);
+                }
+                Some(value) => {
+                    if URL_ATTRIBUTES.contains(&name.as_str()) && !Self::is_safe_url(&value) {
+                        continue;
+                    }
+                    sanitized.push_str(&format!(" {}=\"{}\"", name, escape_html(&value)));
+                }
+            }
+        }
+        sanitized
+    }
+
+    // attribute values as browsers read them (e.g. `javascript&#58;` => `javascript:`),
+    // where numeric references may lack the trailing `;`
+    fn decode_entities(value: &str) -> String {
+        let entity_regex = regex!(r"&#[xX]([0-9a-fA-F]+);?|&#([0-9]+);?|&[A-Za-z][A-Za-z0-9]*;");
+
+        entity_regex
+            .replace_all(value, |captures: &regex::Captures| {
+                let code = match (captures.get(1), captures.get(2)) {
+                    (Some(hex), _) => u32::from_str_radix(hex.as_str(), 16).ok(),
+                    (_, Some(decimal)) => decimal.as_str().parse().ok(),
+                    _ => {
+                        return get_entity_from_str(&captures[0])
+                            .unwrap_or(&captures[0])
+                            .to_string()
+                    }
+                };
+                code.filter(|code| is_valid_entity_code(*code))
+                    .and_then(char::from_u32)
+                    .unwrap_or(char::REPLACEMENT_CHARACTER)
+                    .to_string()
+            })
+            .into_owned()
+    }
+
+    fn is_safe_url(url: &str) -> bool {
+        // browsers ignore whitespace and control characters in schemes (e.g. `java\tscript:`)
+        let normalized: String = url
+            .chars()
+            .filter(|c| !c.is_whitespace() && !c.is_control())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        !DISALLOWED_URL_SCHEMES
+            .iter()
+            .any(|scheme| normalized.starts_with(scheme))
+    }
+}
+
+struct HtmlSanitizationRule;
+
+impl CoreRule for HtmlSanitizationRule {
+    // a custom function that will be invoked once per document.
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let sanitizer = match md.ext.get::<MarkdonwItOptions>() {
+            Some(options) => HtmlSanitizer::new(options),
+            None => HtmlSanitizer::new(&MarkdonwItOptions::default()),
+        };
+
+        // children first, so that blocks emptied by the sanitizer can be removed from their parent
+        root.walk_post_mut(|node, _| {
+            if let Some(html) = node.cast::<HtmlBlock>() {
+                let content = sanitizer.sanitize(&html.content);
+                node.replace(HtmlBlock { content });
+            } else if let Some(html) = node.cast::<HtmlInline>() {
+                let content = sanitizer.sanitize(&html.content);
+                node.replace(HtmlInline { content });
+            }
+
+            node.children
+                .retain(|child| match child.cast::<HtmlBlock>() {
+                    Some(html) => !html.content.trim().is_empty(),
+                    None => true,
+                });
+        });
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.add_rule::<HtmlSanitizationRule>();
+}
+
+#[test]
+fn test_html_sanitization() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::html::add(&mut md);
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    {
+        // allowed tags are kept
+        let src = "<div class=\"note\" id=\"note-1\"> HTML tag is here </div>\n\nsome <strong>bold</strong> text";
+        let html = md.parse(src).render();
+
+        assert_eq!(
+            html,
+            "<div class=\"note\" id=\"note-1\"> HTML tag is here </div>\n<p>some <strong>bold</strong> text</p>\n"
+        );
+    }
+
+    {
+        // script and style are removed along with their contents
+        let src = "<script>alert('xss')</script>\n\n<style>body { display: none; }</style>\n\ntext";
+        let html = md.parse(src).render();
+
+        assert_eq!(html, "<p>text</p>\n");
+    }
+
+    {
+        // event handlers and unknown attributes are dropped
+        let src = "<div onclick=\"alert(1)\" style=\"color: red\" ONMOUSEOVER='alert(2)' title=\"a &quot;b&quot;\">text</div>";
+        let html = md.parse(src).render();
+
+        assert_eq!(html, "<div title=\"a &quot;b&quot;\">text</div>\n");
+    }
+
+    {
+        // unsafe urls are dropped
+        let src = "<a href=\"javascript:alert(1)\">foo</a> <a href=\" JAVA\tSCRIPT:alert(1)\">bar</a> <img src=\"data:image/svg+xml;base64,xxx\" alt=\"baz\"> <a href=\"https://example.com\">qux</a>";
+        let html = md.parse(src).render();
+
+        assert_eq!(
+            html,
+            "<p><a>foo</a> <a>bar</a> <img alt=\"baz\"> <a href=\"https://example.com\">qux</a></p>\n"
+        );
+    }
+
+    {
+        // unsafe urls hidden by character references are dropped
+        let src = "<a href=\"javascript&#58;alert(1)\">foo</a> <a href=\"&#106;avascript:alert(1)\">bar</a> <a href=\"&#x6A&#x61vascript&colon;alert(1)\">baz</a> <a href=\"/search?a=1&amp;b=&lt;2&gt;\">qux</a>";
+        let html = md.parse(src).render();
+
+        assert_eq!(
+            html,
+            "<p><a>foo</a> <a>bar</a> <a>baz</a> <a href=\"/search?a=1&amp;b=&lt;2&gt;\">qux</a></p>\n"
+        );
+    }
+
+    {
+        // tags outside of the allowlist are removed, but their text remains
+        let src = "<iframe src=\"https://example.com\"></iframe>\n\nsome <marquee>moving</marquee> text<!-- comment -->";
+        let html = md.parse(src).render();
+
+        assert_eq!(html, "<p>some moving text</p>\n");
+    }
+
+    {
+        // self-closing tags
+        let src = "line<br/>break<hr />";
+        let html = md.parse(src).render();
+
+        assert_eq!(html, "<p>line<br />break<hr /></p>\n");
+    }
+
+    {
+        // with options
+        let options = MarkdonwItOptions::new(HashMap::from([
+            (
+                "html_allowed_tags".to_string(),
+                OptionValue::from("span, iframe"),
+            ),
+            (
+                "html_allowed_attributes".to_string(),
+                OptionValue::from("style,src"),
+            ),
+        ]))
+        .unwrap();
+        options.add(&mut md);
+
+        let src = "<iframe src=\"https://example.com\" width=\"100\"></iframe>\n\n<div style=\"color: red\"><span style=\"color: red\">text</span></div>";
+        let html = md.parse(src).render();
+
+        assert_eq!(
+            html,
+            "<iframe src=\"https://example.com\"></iframe>\n<span style=\"color: red\">text</span>\n"
+        );
+    }
+}
@@ -0,0 +1,148 @@
+// images without alt text, which accessibility audits flag
+// (`missing_alt_text: "error"` fails the conversion, listing the lines of such images)
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::{MarkdownIt, Node};
+
+use crate::driver::MarkdonwItOptions;
+use crate::extensions::diagnostics::{Diagnostics, SourceLines};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MissingAltText {
+    // rendered as they are (default)
+    Allow,
+    // the alt text is filled with `alt_text_placeholder`
+    Placeholder,
+    // reported as warnings along with the output
+    Warning,
+    // the conversion fails
+    Error,
+}
+
+impl MissingAltText {
+    fn new(value: &str) -> Self {
+        match value {
+            "placeholder" => Self::Placeholder,
+            "warning" => Self::Warning,
+            "error" => Self::Error,
+            _ => Self::Allow,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ImageAltTextRule;
+
+impl CoreRule for ImageAltTextRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(options) = md.ext.get::<MarkdonwItOptions>() else {
+            return;
+        };
+        let missing_alt_text =
+            MissingAltText::new(&options.get_option_or_default("missing_alt_text", "allow"));
+        let placeholder = options.get_option_or_default("alt_text_placeholder", "画像");
+
+        let source_lines = SourceLines::new(root);
+        let mut messages = Vec::new();
+
+        root.walk_mut(|node, _| {
+            let Some(image) = node.cast::<Image>() else {
+                return;
+            };
+            if !node.collect_text().trim().is_empty() {
+                return;
+            }
+
+            match missing_alt_text {
+                MissingAltText::Allow => {}
+                MissingAltText::Placeholder => {
+                    node.children = vec![Node::new(Text {
+                        content: placeholder.clone(),
+                    })];
+                }
+                MissingAltText::Warning | MissingAltText::Error => {
+                    messages.push(source_lines.message(
+                        node.srcmap,
+                        &format!("image without alt text ({})", image.url),
+                    ));
+                }
+            }
+        });
+
+        if missing_alt_text == MissingAltText::Error {
+            Diagnostics::add_errors(root, messages);
+        } else {
+            Diagnostics::add_warnings(root, messages);
+        }
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.add_rule::<ImageAltTextRule>();
+}
+
+#[test]
+fn test_missing_alt_text() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let parse = |md: &mut MarkdownIt, missing_alt_text: &str| {
+        MarkdonwItOptions::new(HashMap::from([(
+            "missing_alt_text".to_string(),
+            OptionValue::from(missing_alt_text),
+        )]))
+        .unwrap()
+        .add(md);
+        md.parse("![画像タイトル](/a.jpg)\n\n![](/b.jpg)\n\ntext ![ ](/c.jpg)")
+    };
+
+    {
+        // allow
+        let root = parse(&mut md, "allow");
+        assert_eq!(
+            root.render(),
+            "<p><img src=\"/a.jpg\" alt=\"画像タイトル\"></p>\n<p><img src=\"/b.jpg\" alt=\"\"></p>\n<p>text <img src=\"/c.jpg\" alt=\" \"></p>\n"
+        );
+        assert!(root.ext.get::<Diagnostics>().is_none());
+    }
+
+    {
+        // placeholder
+        let root = parse(&mut md, "placeholder");
+        assert_eq!(
+            root.render(),
+            "<p><img src=\"/a.jpg\" alt=\"画像タイトル\"></p>\n<p><img src=\"/b.jpg\" alt=\"画像\"></p>\n<p>text <img src=\"/c.jpg\" alt=\"画像\"></p>\n"
+        );
+    }
+
+    {
+        // warning
+        let root = parse(&mut md, "warning");
+        let diagnostics = root.ext.get::<Diagnostics>().unwrap();
+        assert!(diagnostics.errors.is_empty());
+        assert_eq!(
+            diagnostics.warnings,
+            vec![
+                "line 3: image without alt text (/b.jpg)",
+                "line 5: image without alt text (/c.jpg)",
+            ]
+        );
+    }
+
+    {
+        // error
+        let root = parse(&mut md, "error");
+        assert_eq!(
+            root.ext.get::<Diagnostics>().unwrap().errors,
+            vec![
+                "line 3: image without alt text (/b.jpg)",
+                "line 5: image without alt text (/c.jpg)",
+            ]
+        );
+    }
+}
@@ -0,0 +1,277 @@
+// html attributes of images: lazy loading, a class, dimensions and srcset of resized variants
+//
+// e.g. `<img class="content-image" width="800" height="600" loading="lazy" decoding="async" src="/a.jpg" alt="">`
+use markdown_it::parser::core::CoreRule;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::{MarkdownIt, Node};
+use std::collections::HashMap;
+use url::{form_urlencoded, ParseError, Url};
+
+use crate::driver::{MarkdonwItOptions, OptionValue};
+use crate::extensions::{add_class, base_url};
+
+// the extension is added when any of them is given
+pub const OPTIONS: [&str; 6] = [
+    "image_lazy_loading",
+    "image_class",
+    "image_dimensions",
+    "image_srcset_template",
+    "image_srcset_widths",
+    "image_sizes",
+];
+
+#[derive(Debug)]
+struct ImageAttributes {
+    lazy_loading: bool,
+    class: Option<String>,
+    // width and height by url (a callback is resolved into this on the Ruby side)
+    dimensions: HashMap<String, (i64, i64)>,
+    // e.g. `https://img.example.com/w{width}{path}` or `https://resize.example.com/?w={width}&url={url}`
+    srcset_template: Option<String>,
+    srcset_widths: Vec<i64>,
+    sizes: Option<String>,
+    // `{url}` of srcset is resolved against it, as `src` is later on
+    asset_base_url: Option<Url>,
+}
+
+impl ImageAttributes {
+    fn new(options: &MarkdonwItOptions) -> Self {
+        Self {
+            lazy_loading: options.is_enabled("image_lazy_loading", false),
+            class: options.get_string("image_class"),
+            dimensions: Self::dimensions(options),
+            srcset_template: options.get_string("image_srcset_template"),
+            srcset_widths: options.get_integer_list("image_srcset_widths"),
+            sizes: options.get_string("image_sizes"),
+            asset_base_url: base_url::asset_base_url(options),
+        }
+    }
+
+    // e.g. `{ "/a.jpg" => { "width" => 800, "height" => 600 } }`
+    fn dimensions(options: &MarkdonwItOptions) -> HashMap<String, (i64, i64)> {
+        let Some(OptionValue::Map(images)) = options.get_option("image_dimensions") else {
+            return HashMap::new();
+        };
+        images
+            .iter()
+            .filter_map(|(url, dimension)| match dimension {
+                OptionValue::Map(dimension) => {
+                    match (dimension.get("width"), dimension.get("height")) {
+                        (Some(OptionValue::Integer(width)), Some(OptionValue::Integer(height))) => {
+                            Some((url.clone(), (*width, *height)))
+                        }
+                        _ => None,
+                    }
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn add_attrs(&self, url: &str, attrs: &mut Vec<(&'static str, String)>) {
+        if let Some(class) = &self.class {
+            add_class(attrs, class);
+        }
+        if let Some((width, height)) = self.dimensions.get(url) {
+            attrs.push(("width", width.to_string()));
+            attrs.push(("height", height.to_string()));
+        }
+        if let Some(srcset) = self.srcset(url) {
+            attrs.push(("srcset", srcset));
+            if let Some(sizes) = &self.sizes {
+                attrs.push(("sizes", sizes.clone()));
+            }
+        }
+        if self.lazy_loading {
+            attrs.push(("loading", "lazy".to_string()));
+            attrs.push(("decoding", "async".to_string()));
+        }
+    }
+
+    // `{url}` is replaced with the url-encoded url (as `image_proxy_url` does), resolved against
+    // `asset_base_url` or `base_url` if any, and `{path}` with the url as written, for relative urls only
+    fn srcset(&self, url: &str) -> Option<String> {
+        let template = self.srcset_template.as_ref()?;
+        if self.srcset_widths.is_empty() {
+            return None;
+        }
+        // e.g. not `https://img.example.com/w320https://example.com/a.jpg`
+        let is_relative = matches!(Url::parse(url), Err(ParseError::RelativeUrlWithoutBase))
+            && !url.starts_with("//");
+        if template.contains("{path}") && !is_relative {
+            return None;
+        }
+
+        let resolved = match &self.asset_base_url {
+            Some(asset_base_url) => base_url::resolve(asset_base_url, url),
+            None => url.to_string(),
+        };
+        let encoded: String = form_urlencoded::byte_serialize(resolved.as_bytes()).collect();
+        let srcset: Vec<String> = self
+            .srcset_widths
+            .iter()
+            .map(|width| {
+                let src = template
+                    .replace("{width}", &width.to_string())
+                    .replace("{url}", &encoded)
+                    .replace("{path}", url);
+                format!("{} {}w", src, width)
+            })
+            .collect();
+        Some(srcset.join(", "))
+    }
+}
+
+#[derive(Debug)]
+struct ImageAttributesRule;
+
+impl CoreRule for ImageAttributesRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(options) = md.ext.get::<MarkdonwItOptions>() else {
+            return;
+        };
+        let image_attributes = ImageAttributes::new(options);
+
+        root.walk_mut(|node, _| {
+            if let Some(image) = node.cast::<Image>() {
+                let url = image.url.clone();
+                image_attributes.add_attrs(&url, &mut node.attrs);
+            }
+        });
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.add_rule::<ImageAttributesRule>();
+}
+
+#[test]
+fn test_image_attributes() {
+    use std::collections::BTreeMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let src = "![画像タイトル](/images/1226-0009big.jpg) ![](/images/other.png)";
+
+    {
+        // lazy loading and class
+        MarkdonwItOptions::new(HashMap::from([
+            ("image_lazy_loading".to_string(), OptionValue::from(true)),
+            (
+                "image_class".to_string(),
+                OptionValue::from("content-image"),
+            ),
+        ]))
+        .unwrap()
+        .add(&mut md);
+
+        assert_eq!(
+            md.parse(src).render(),
+            "<p><img class=\"content-image\" loading=\"lazy\" decoding=\"async\" src=\"/images/1226-0009big.jpg\" alt=\"画像タイトル\"> <img class=\"content-image\" loading=\"lazy\" decoding=\"async\" src=\"/images/other.png\" alt=\"\"></p>\n"
+        );
+
+        // the class is added to that of other plugins
+        let mut attrs = vec![("class", "emoji".to_string())];
+        ImageAttributes::new(md.ext.get::<MarkdonwItOptions>().unwrap())
+            .add_attrs("/images/other.png", &mut attrs);
+        assert_eq!(
+            attrs,
+            vec![
+                ("class", "emoji content-image".to_string()),
+                ("loading", "lazy".to_string()),
+                ("decoding", "async".to_string()),
+            ]
+        );
+    }
+
+    {
+        // dimensions and srcset
+        let dimension = |width: i64, height: i64| {
+            OptionValue::Map(BTreeMap::from([
+                ("width".to_string(), OptionValue::from(width)),
+                ("height".to_string(), OptionValue::from(height)),
+            ]))
+        };
+        MarkdonwItOptions::new(HashMap::from([
+            (
+                "image_dimensions".to_string(),
+                OptionValue::Map(BTreeMap::from([(
+                    "/images/1226-0009big.jpg".to_string(),
+                    dimension(1200, 800),
+                )])),
+            ),
+            (
+                "image_srcset_template".to_string(),
+                OptionValue::from("https://img.example.com/w{width}{path}"),
+            ),
+            (
+                "image_srcset_widths".to_string(),
+                OptionValue::List(vec![OptionValue::from(320), OptionValue::from(640)]),
+            ),
+            (
+                "image_sizes".to_string(),
+                OptionValue::from("(max-width: 640px) 100vw, 640px"),
+            ),
+        ]))
+        .unwrap()
+        .add(&mut md);
+
+        assert_eq!(
+            md.parse(src).render(),
+            "<p><img width=\"1200\" height=\"800\" srcset=\"https://img.example.com/w320/images/1226-0009big.jpg 320w, https://img.example.com/w640/images/1226-0009big.jpg 640w\" sizes=\"(max-width: 640px) 100vw, 640px\" src=\"/images/1226-0009big.jpg\" alt=\"画像タイトル\"> <img srcset=\"https://img.example.com/w320/images/other.png 320w, https://img.example.com/w640/images/other.png 640w\" sizes=\"(max-width: 640px) 100vw, 640px\" src=\"/images/other.png\" alt=\"\"></p>\n"
+        );
+
+        // no srcset of absolute urls by paths
+        assert_eq!(
+            md.parse("![](https://example.com/a.jpg)").render(),
+            "<p><img src=\"https://example.com/a.jpg\" alt=\"\"></p>\n"
+        );
+    }
+
+    {
+        // srcset by url-encoded urls
+        MarkdonwItOptions::new(HashMap::from([
+            (
+                "image_srcset_template".to_string(),
+                OptionValue::from("https://resize.example.com/?w={width}&url={url}"),
+            ),
+            ("image_srcset_widths".to_string(), OptionValue::from("320")),
+        ]))
+        .unwrap()
+        .add(&mut md);
+
+        assert_eq!(
+            md.parse("![](https://example.com/a.jpg) ![](/b.png)").render(),
+            "<p><img srcset=\"https://resize.example.com/?w=320&amp;url=https%3A%2F%2Fexample.com%2Fa.jpg 320w\" src=\"https://example.com/a.jpg\" alt=\"\"> <img srcset=\"https://resize.example.com/?w=320&amp;url=%2Fb.png 320w\" src=\"/b.png\" alt=\"\"></p>\n"
+        );
+    }
+
+    {
+        // srcset by urls resolved against `asset_base_url`, as src is
+        let mut md = MarkdownIt::new();
+        markdown_it::plugins::cmark::add(&mut md);
+        add(&mut md);
+        base_url::add(&mut md);
+        MarkdonwItOptions::new(HashMap::from([
+            (
+                "image_srcset_template".to_string(),
+                OptionValue::from("https://resize.example.com/?w={width}&url={url}"),
+            ),
+            ("image_srcset_widths".to_string(), OptionValue::from("320")),
+            (
+                "asset_base_url".to_string(),
+                OptionValue::from("https://cdn.example.com/"),
+            ),
+        ]))
+        .unwrap()
+        .add(&mut md);
+
+        assert_eq!(
+            md.parse("![](/b.png)").render(),
+            "<p><img srcset=\"https://resize.example.com/?w=320&amp;url=https%3A%2F%2Fcdn.example.com%2Fb.png 320w\" src=\"https://cdn.example.com/b.png\" alt=\"\"></p>\n"
+        );
+    }
+}
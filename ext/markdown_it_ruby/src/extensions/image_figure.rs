@@ -0,0 +1,109 @@
+// images standing alone in paragraphs, rendered as captioned figures (e.g. photos of news pages)
+//
+// e.g. `![alt](src "caption")` => `<figure><img src="src" alt="alt" title="caption"><figcaption>caption</figcaption></figure>`
+use markdown_it::parser::core::CoreRule;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+use crate::driver::MarkdonwItOptions;
+
+#[derive(Debug)]
+pub struct ImageFigure {
+    // the title of the image
+    pub caption: Option<String>,
+    pub class: Option<String>,
+}
+
+impl NodeValue for ImageFigure {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        let mut attrs = node.attrs.clone();
+        if let Some(class) = &self.class {
+            attrs.push(("class", class.clone()));
+        }
+
+        fmt.cr();
+        fmt.open("figure", &attrs);
+        fmt.contents(&node.children);
+        if let Some(caption) = &self.caption {
+            fmt.open("figcaption", &[]);
+            fmt.text(caption);
+            fmt.close("figcaption");
+        }
+        fmt.close("figure");
+        fmt.cr();
+    }
+}
+
+#[derive(Debug)]
+struct ImageFigureRule;
+
+impl CoreRule for ImageFigureRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(options) = md.ext.get::<MarkdonwItOptions>() else {
+            return;
+        };
+        // "titled" (images with titles only) or "standalone" (all the images standing alone)
+        let titled_only = options.get_option_or_default("image_figures", "titled") == "titled";
+        let class = options.get_string("image_figure_class");
+
+        root.walk_mut(|node, _| {
+            if !node.is::<Paragraph>() || node.children.len() != 1 {
+                return;
+            }
+            let Some(image) = node.children[0].cast::<Image>() else {
+                return;
+            };
+            if titled_only && image.title.is_none() {
+                return;
+            }
+
+            node.replace(ImageFigure {
+                caption: image.title.clone(),
+                class: class.clone(),
+            });
+        });
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.add_rule::<ImageFigureRule>();
+}
+
+#[test]
+fn test_image_figure() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let src = "![市長](/a.jpg \"記者会見する市長\")\n\n![風景](/b.jpg)\n\ntext ![アイコン](/c.png \"icon\")";
+    let render = |md: &mut MarkdownIt, options: Vec<(&str, &str)>| {
+        MarkdonwItOptions::new(
+            options
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), OptionValue::from(value)))
+                .collect::<HashMap<_, _>>(),
+        )
+        .unwrap()
+        .add(md);
+        md.parse(src).render()
+    };
+
+    assert_eq!(
+        render(&mut md, vec![("image_figures", "titled")]),
+        "<figure><img src=\"/a.jpg\" alt=\"市長\" title=\"記者会見する市長\"><figcaption>記者会見する市長</figcaption></figure>\n<p><img src=\"/b.jpg\" alt=\"風景\"></p>\n<p>text <img src=\"/c.png\" alt=\"アイコン\" title=\"icon\"></p>\n"
+    );
+    assert_eq!(
+        render(
+            &mut md,
+            vec![
+                ("image_figures", "standalone"),
+                ("image_figure_class", "photo")
+            ]
+        ),
+        "<figure class=\"photo\"><img src=\"/a.jpg\" alt=\"市長\" title=\"記者会見する市長\"><figcaption>記者会見する市長</figcaption></figure>\n<figure class=\"photo\"><img src=\"/b.jpg\" alt=\"風景\"></figure>\n<p>text <img src=\"/c.png\" alt=\"アイコン\" title=\"icon\"></p>\n"
+    );
+}
@@ -0,0 +1,194 @@
+// external images served through an image proxy, not to leak readers' IP addresses to other sites
+// and to keep images available after the sources disappear
+//
+// e.g. with `image_proxy_url: "https://img.example.jp/proxy?url={url}&sig={hmac}"`,
+// `![](https://example.com/a.jpg)` => `<img src="https://img.example.jp/proxy?url=https%3A%2F%2Fexample.com%2Fa.jpg&sig=78c0...">`
+use hmac::{Hmac, Mac};
+use markdown_it::parser::core::CoreRule;
+use markdown_it::plugins::cmark::inline::image::Image;
+use markdown_it::{MarkdownIt, Node};
+use sha2::Sha256;
+use url::{form_urlencoded, ParseError, Url};
+
+use crate::driver::{InternalDomain, MarkdonwItOptions};
+
+#[derive(Debug)]
+struct ImageProxy {
+    // `{url}` is replaced with the url-encoded image url, and `{hmac}` with its signature
+    template: String,
+    key: Option<String>,
+    // images of the site are not proxied
+    internal_domain: Option<InternalDomain>,
+}
+
+impl ImageProxy {
+    fn new(options: &MarkdonwItOptions) -> Self {
+        Self {
+            template: options.get_option_or_default("image_proxy_url", "{url}"),
+            key: options.get_string("image_proxy_key"),
+            internal_domain: options.internal_domain(),
+        }
+    }
+
+    // `None` for images which stay direct (e.g. relative paths and those of internal domains)
+    fn proxy_url(&self, url: &str) -> Option<String> {
+        let absolute_url = match Url::parse(url) {
+            Ok(parsed) if parsed.scheme() == "http" || parsed.scheme() == "https" => {
+                url.to_string()
+            }
+            // e.g. `//example.com/a.gif`, fetched as `https://example.com/a.gif` by the proxy
+            Err(ParseError::RelativeUrlWithoutBase) => {
+                InternalDomain::other_host_url(url)?.to_string()
+            }
+            _ => return None,
+        };
+        if let Some(internal_domain) = &self.internal_domain {
+            if internal_domain.matches(url) {
+                return None;
+            }
+        }
+
+        let encoded: String = form_urlencoded::byte_serialize(absolute_url.as_bytes()).collect();
+        let mut proxy_url = self.template.replace("{url}", &encoded);
+        if let Some(key) = &self.key {
+            proxy_url = proxy_url.replace("{hmac}", &sign(key, &absolute_url));
+        }
+        Some(proxy_url)
+    }
+
+    // e.g. `https://resize.example.com/?w=320&url=... 320w, ...` by `image_srcset_template`
+    fn proxy_srcset(&self, srcset: &str) -> String {
+        srcset
+            .split(", ")
+            .map(|candidate| {
+                let (url, descriptor) = candidate.split_once(' ').unwrap_or((candidate, ""));
+                match self.proxy_url(url) {
+                    Some(proxy_url) if descriptor.is_empty() => proxy_url,
+                    Some(proxy_url) => format!("{} {}", proxy_url, descriptor),
+                    None => candidate.to_string(),
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// HMAC-SHA256 of the url in lowercase hex, for the proxy to verify that urls are issued by the site
+fn sign(key: &str, url: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key.as_bytes()).expect("HMAC can take a key of any size");
+    mac.update(url.as_bytes());
+    mac.finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+#[derive(Debug)]
+struct ImageProxyRule;
+
+impl CoreRule for ImageProxyRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let Some(options) = md.ext.get::<MarkdonwItOptions>() else {
+            return;
+        };
+        let image_proxy = ImageProxy::new(options);
+
+        root.walk_mut(|node, _| {
+            if let Some(image) = node.cast_mut::<Image>() {
+                if let Some(proxy_url) = image_proxy.proxy_url(&image.url) {
+                    image.url = proxy_url;
+                }
+                // srcset added by image attributes, not to load external images directly either
+                for (key, value) in node.attrs.iter_mut() {
+                    if *key == "srcset" {
+                        *value = image_proxy.proxy_srcset(value);
+                    }
+                }
+            }
+        });
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    md.add_rule::<ImageProxyRule>();
+}
+
+#[test]
+fn test_sign() {
+    assert_eq!(
+        sign("secret", "https://example.com/a.jpg"),
+        "78c051af4dca895687bc48f1ea0de0fde3013cc14157ce78b0c81ba3ef2418f7"
+    );
+}
+
+#[test]
+fn test_image_proxy() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    MarkdonwItOptions::new(HashMap::from([
+        (
+            "internal_domain_name".to_string(),
+            OptionValue::from("kyoto.dosue.jp"),
+        ),
+        (
+            "image_proxy_url".to_string(),
+            OptionValue::from("https://img.example.jp/proxy?url={url}&sig={hmac}"),
+        ),
+        ("image_proxy_key".to_string(), OptionValue::from("secret")),
+    ]))
+    .unwrap()
+    .add(&mut md);
+
+    let html = md
+        .parse("![a](https://example.com/a.jpg) ![b](https://kyoto.dosue.jp/b.jpg) ![c](/c.jpg) ![d](data:image/png;base64,AAAA)")
+        .render();
+    assert_eq!(
+        html,
+        "<p><img src=\"https://img.example.jp/proxy?url=https%3A%2F%2Fexample.com%2Fa.jpg&amp;sig=78c051af4dca895687bc48f1ea0de0fde3013cc14157ce78b0c81ba3ef2418f7\" alt=\"a\"> <img src=\"https://kyoto.dosue.jp/b.jpg\" alt=\"b\"> <img src=\"/c.jpg\" alt=\"c\"> <img src=\"data:image/png;base64,AAAA\" alt=\"d\"></p>\n"
+    );
+}
+
+#[test]
+fn test_image_proxy_other_hosts() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    crate::extensions::image_attributes::add(&mut md);
+    add(&mut md);
+
+    MarkdonwItOptions::new(HashMap::from([
+        (
+            "internal_domain_name".to_string(),
+            OptionValue::from("kyoto.dosue.jp"),
+        ),
+        (
+            "image_proxy_url".to_string(),
+            OptionValue::from("https://img.example.jp/proxy?url={url}"),
+        ),
+        (
+            "image_srcset_template".to_string(),
+            OptionValue::from("https://resize.example.com/?w={width}&url={url}"),
+        ),
+        ("image_srcset_widths".to_string(), OptionValue::from("320")),
+    ]))
+    .unwrap()
+    .add(&mut md);
+
+    // protocol-relative urls, and srcset of external resizers
+    let html = md
+        .parse("![a](//tracker.example.net/a.gif) ![b](//kyoto.dosue.jp/b.gif)")
+        .render();
+    assert_eq!(
+        html,
+        "<p><img srcset=\"https://img.example.jp/proxy?url=https%3A%2F%2Fresize.example.com%2F%3Fw%3D320%26url%3D%252F%252Ftracker.example.net%252Fa.gif 320w\" src=\"https://img.example.jp/proxy?url=https%3A%2F%2Ftracker.example.net%2Fa.gif\" alt=\"a\"> <img srcset=\"https://img.example.jp/proxy?url=https%3A%2F%2Fresize.example.com%2F%3Fw%3D320%26url%3D%252F%252Fkyoto.dosue.jp%252Fb.gif 320w\" src=\"//kyoto.dosue.jp/b.gif\" alt=\"b\"></p>\n"
+    );
+}
@@ -2,8 +2,14 @@ use markdown_it::parser::core::CoreRule;
 use markdown_it::plugins::cmark::inline::{autolink::Autolink, link::Link};
 use markdown_it::plugins::extra::linkify::Linkified;
 use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+use url::{ParseError, Url};
 
 use crate::driver::{InternalDomain, MarkdonwItOptions};
+use crate::extensions::file_link::{FileAnnotation, FileLinkPolicy};
+use crate::extensions::{add_class, scheme_less_url};
+
+// `relative` stands for urls without scheme (e.g. `/foo/bar`, `foo.pdf`)
+const DEFAULT_ALLOWED_URL_SCHEMES: [&str; 5] = ["http", "https", "mailto", "tel", "relative"];
 
 #[derive(Debug)]
 pub struct LinkWithTarget {
@@ -11,6 +17,10 @@ pub struct LinkWithTarget {
     pub title: Option<String>,
     pub target: Option<String>,
     pub rel: Option<String>,
+    // for links opened in new windows
+    pub new_window_hint: Option<NewWindowHint>,
+    // for links to documents such as PDF files
+    pub file: Option<FileAnnotation>,
 }
 
 impl NodeValue for LinkWithTarget {
@@ -28,9 +38,18 @@ impl NodeValue for LinkWithTarget {
         if let Some(title) = &self.title {
             attrs.push(("title", title.clone()));
         }
+        if let Some(file) = &self.file {
+            add_class(&mut attrs, &file.class);
+        }
 
         fmt.open("a", &attrs);
         fmt.contents(&node.children);
+        if let Some(file) = &self.file {
+            file.render(fmt);
+        }
+        if let Some(new_window_hint) = &self.new_window_hint {
+            new_window_hint.render(fmt);
+        }
         fmt.close("a");
     }
 }
@@ -40,6 +59,7 @@ impl LinkWithTarget {
         url: String,
         title: Option<String>,
         internal_domain_name: Option<InternalDomain>,
+        external_link_policy: &ExternalLinkPolicy,
     ) -> Self {
         // for internal links only
         if let Some(internal_domain_name) = internal_domain_name {
@@ -49,20 +69,187 @@ impl LinkWithTarget {
                     title,
                     target: None,
                     rel: None,
+                    new_window_hint: None,
+                    file: None,
                 };
             }
         }
 
-        // open external links in a new tab
+        // open external links in a new tab (by default)
         LinkWithTarget {
             url,
             title,
-            target: Some("_blank".to_string()),
-            rel: Some("noopener noreferrer".to_string()),
+            target: external_link_policy.target.clone(),
+            rel: external_link_policy.rel.clone(),
+            new_window_hint: external_link_policy.new_window_hint.clone(),
+            file: None,
+        }
+    }
+}
+
+// tells users that the link opens a new window (as JIS X 8341-3 / WCAG expects),
+// by a visually hidden text for screen readers and/or an icon
+//
+// e.g. `<span class="visually-hidden">（新しいウィンドウで開きます）</span>`
+// or `<span class="icon-external" aria-hidden="true"></span>`
+#[derive(Debug, Clone)]
+pub struct NewWindowHint {
+    text: Option<String>,
+    text_class: String,
+    icon_class: Option<String>,
+}
+
+impl NewWindowHint {
+    fn new(options: &MarkdonwItOptions) -> Option<Self> {
+        let text = options.is_enabled("external_link_hint", false).then(|| {
+            options
+                .get_option_or_default("external_link_hint_text", "（新しいウィンドウで開きます）")
+        });
+        let icon_class = options.get_string("external_link_icon_class");
+        if text.is_none() && icon_class.is_none() {
+            return None;
+        }
+
+        Some(Self {
+            text,
+            text_class: options
+                .get_option_or_default("external_link_hint_class", "visually-hidden"),
+            icon_class,
+        })
+    }
+
+    fn render(&self, fmt: &mut dyn Renderer) {
+        if let Some(text) = &self.text {
+            fmt.open("span", &[("class", self.text_class.clone())]);
+            fmt.text(text);
+            fmt.close("span");
+        }
+        if let Some(icon_class) = &self.icon_class {
+            let attrs = [
+                ("class", icon_class.clone()),
+                ("aria-hidden", "true".to_string()),
+            ];
+            fmt.open("span", &attrs);
+            fmt.close("span");
+        }
+    }
+}
+
+// `target` and `rel` attributes of external links
+//
+// e.g. `external_link_rel: "noopener nofollow ugc"` for user generated contents,
+// `external_link_rel: "noopener"` to send referrers,
+// or `external_link_target: false` not to open new tabs
+#[derive(Debug)]
+pub struct ExternalLinkPolicy {
+    target: Option<String>,
+    rel: Option<String>,
+    new_window_hint: Option<NewWindowHint>,
+}
+
+impl ExternalLinkPolicy {
+    fn new(options: Option<&MarkdonwItOptions>) -> Self {
+        let default_options = MarkdonwItOptions::default();
+        let options = options.unwrap_or(&default_options);
+        let new_tab = options.is_enabled("external_link_target", true);
+
+        let target =
+            new_tab.then(|| options.get_option_or_default("external_link_target", "_blank"));
+        // `noopener noreferrer` only makes sense for new tabs
+        let default_rel = if new_tab { "noopener noreferrer" } else { "" };
+        let rel = if options.is_enabled("external_link_rel", true) {
+            Some(options.get_option_or_default("external_link_rel", default_rel))
+        } else {
+            None
+        };
+
+        // `_self`, `_parent` and `_top` do not open new windows
+        let new_window_hint = match target.as_deref() {
+            Some("_self" | "_parent" | "_top") | None => None,
+            Some(_) => NewWindowHint::new(options),
+        };
+
+        Self {
+            target,
+            rel: rel.filter(|rel| !rel.trim().is_empty()),
+            new_window_hint,
         }
     }
 }
 
+// a link whose url scheme is not allowed, rendered as its plain text
+#[derive(Debug)]
+pub struct DisallowedLink {
+    pub url: String,
+}
+
+impl NodeValue for DisallowedLink {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        fmt.contents(&node.children);
+    }
+}
+
+#[derive(Debug)]
+struct UrlSchemePolicy {
+    allowed_schemes: Vec<String>,
+    // "text" (default) or "empty_href"
+    disallowed_link: String,
+}
+
+impl UrlSchemePolicy {
+    fn new(options: Option<&MarkdonwItOptions>) -> Self {
+        let default_options = MarkdonwItOptions::default();
+        let options = options.unwrap_or(&default_options);
+        let allowed_schemes =
+            options.get_list_or_default("allowed_url_schemes", &DEFAULT_ALLOWED_URL_SCHEMES);
+
+        Self {
+            allowed_schemes: allowed_schemes
+                .iter()
+                .map(|scheme| scheme.trim().trim_end_matches(':').to_ascii_lowercase())
+                .collect(),
+            disallowed_link: options.get_option_or_default("disallowed_link", "text"),
+        }
+    }
+
+    fn is_allowed(&self, url: &str) -> bool {
+        let scheme = match Url::parse(url) {
+            Ok(url) => url.scheme().to_string(),
+            Err(ParseError::RelativeUrlWithoutBase) => "relative".to_string(),
+            // e.g. `http://` without host
+            Err(_) => return false,
+        };
+        self.allowed_schemes.contains(&scheme)
+    }
+
+    fn replace_disallowed(&self, node: &mut Node, url: String, title: Option<String>) {
+        if self.disallowed_link == "empty_href" {
+            node.replace(LinkWithTarget {
+                url: String::new(),
+                title,
+                target: None,
+                rel: None,
+                new_window_hint: None,
+                file: None,
+            });
+        } else {
+            node.replace(DisallowedLink { url });
+        }
+    }
+}
+
+// url and title of links in markdown (e.g. `[foo](url)`, `<url>` or a bare url)
+fn link_url(node: &Node) -> Option<(String, Option<String>)> {
+    if let Some(link) = node.cast::<Link>() {
+        Some((link.url.clone(), link.title.clone()))
+    } else if let Some(autolink) = node.cast::<Autolink>() {
+        Some((autolink.url.clone(), None))
+    } else {
+        node.cast::<Linkified>()
+            .map(|linkified| (linkified.url.clone(), None))
+    }
+}
+
 struct LinkTargetRule;
 
 impl CoreRule for LinkTargetRule {
@@ -74,23 +261,78 @@ impl CoreRule for LinkTargetRule {
             Some(options) => options.internal_domain(),
         };
 
+        let url_scheme_policy = UrlSchemePolicy::new(options);
+        let external_link_policy = ExternalLinkPolicy::new(options);
+        let file_link_policy = FileLinkPolicy::new(options);
+        // "internal" (default), "external" or "https"
+        let scheme_less_urls = match options {
+            None => "internal".to_string(),
+            Some(options) => options.get_option_or_default("scheme_less_urls", "internal"),
+        };
+
         // walk through AST recursively
         root.walk_mut(|node, _| {
-            if let Some(link) = node.cast::<Link>() {
-                let link_with_target = LinkWithTarget::new(
-                    link.url.clone(),
-                    link.title.clone(),
-                    internal_domain.clone(),
-                );
-                node.replace::<LinkWithTarget>(link_with_target);
-            } else if let Some(autolink) = node.cast::<Autolink>() {
-                let link_with_target =
-                    LinkWithTarget::new(autolink.url.clone(), None, internal_domain.clone());
-                node.replace::<LinkWithTarget>(link_with_target);
-            } else if let Some(linkified) = node.cast::<Linkified>() {
-                let link_with_target =
-                    LinkWithTarget::new(linkified.url.clone(), None, internal_domain.clone());
+            let Some((url, title)) = link_url(node) else {
+                return;
+            };
+
+            // e.g. `google.com/foo`, which is a relative path unless it is treated as a hostname
+            let is_scheme_less_url = scheme_less_urls != "internal"
+                && matches!(Url::parse(&url), Err(ParseError::RelativeUrlWithoutBase))
+                && scheme_less_url::hostname(&url).is_some();
+            let (url, internal_domain) = match scheme_less_urls.as_str() {
+                "https" if is_scheme_less_url => {
+                    (format!("https://{}", url), internal_domain.clone())
+                }
+                "external" if is_scheme_less_url => (url, None),
+                _ => (url, internal_domain.clone()),
+            };
+
+            if url_scheme_policy.is_allowed(&url) {
+                let mut link_with_target =
+                    LinkWithTarget::new(url, title, internal_domain, &external_link_policy);
+                if let Some(file_link_policy) = &file_link_policy {
+                    link_with_target.file = file_link_policy.annotation(&link_with_target.url);
+                }
                 node.replace::<LinkWithTarget>(link_with_target);
+            } else {
+                url_scheme_policy.replace_disallowed(node, url, title);
+            }
+        });
+    }
+}
+
+// the url scheme check only, for `internal_domain_name: false` (e.g. the commonmark preset),
+// which leaves allowed links as they are, except for links to files with `file_link_annotation`
+struct UrlSchemeRule;
+
+impl CoreRule for UrlSchemeRule {
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let options = md.ext.get::<MarkdonwItOptions>();
+        let url_scheme_policy = UrlSchemePolicy::new(options);
+        let file_link_policy = FileLinkPolicy::new(options);
+
+        root.walk_mut(|node, _| {
+            let Some((url, title)) = link_url(node) else {
+                return;
+            };
+            if !url_scheme_policy.is_allowed(&url) {
+                url_scheme_policy.replace_disallowed(node, url, title);
+                return;
+            }
+
+            let file = file_link_policy
+                .as_ref()
+                .and_then(|file_link_policy| file_link_policy.annotation(&url));
+            if file.is_some() {
+                node.replace(LinkWithTarget {
+                    url,
+                    title,
+                    target: None,
+                    rel: None,
+                    new_window_hint: None,
+                    file,
+                });
             }
         });
     }
@@ -100,8 +342,13 @@ pub fn add(md: &mut MarkdownIt) {
     md.add_rule::<LinkTargetRule>();
 }
 
+pub fn add_url_scheme_check(md: &mut MarkdownIt) {
+    md.add_rule::<UrlSchemeRule>();
+}
+
 #[test]
 fn test_link_with_target() {
+    use crate::driver::OptionValue;
     use std::collections::HashMap;
 
     let mut md = MarkdownIt::new();
@@ -292,8 +539,9 @@ fn test_link_with_target() {
         let base_url = "https://kyoto.dosue.jp".to_string();
         let options = MarkdonwItOptions::new(HashMap::from([(
             "internal_domain_name".to_string(),
-            base_url.to_string(),
-        )]));
+            OptionValue::from(base_url.as_str()),
+        )]))
+        .unwrap();
         options.add(&mut md);
 
         {
@@ -471,3 +719,373 @@ fn test_link_with_target() {
         }
     }
 }
+
+#[test]
+fn test_link_url_schemes() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it::plugins::extra::add(&mut md);
+    add(&mut md);
+
+    {
+        // without options
+        {
+            // allowed schemes
+            let src = "[foo](mailto:foo@example.com) [bar](tel:0000000000) [baz](/foo/bar.pdf)";
+            let html = md.parse(src).render();
+            assert_eq!(
+                html,
+                "<p><a href=\"mailto:foo@example.com\" target=\"_blank\" rel=\"noopener noreferrer\">foo</a> <a href=\"tel:0000000000\" target=\"_blank\" rel=\"noopener noreferrer\">bar</a> <a href=\"/foo/bar.pdf\" target=\"_blank\" rel=\"noopener noreferrer\">baz</a></p>\n"
+            );
+        }
+        {
+            // disallowed schemes are rendered as plain text
+            let src = "[some *title*](htts:/www.city.fuji.shizuoka.jp) [foo](ftp://example.com) <ssh://example.com>";
+            let html = md.parse(src).render();
+            assert_eq!(html, "<p>some <em>title</em> foo ssh://example.com</p>\n");
+        }
+    }
+
+    {
+        // with options
+        let options = MarkdonwItOptions::new(HashMap::from([
+            (
+                "allowed_url_schemes".to_string(),
+                OptionValue::from("https, ftp:"),
+            ),
+            (
+                "disallowed_link".to_string(),
+                OptionValue::from("empty_href"),
+            ),
+        ]))
+        .unwrap();
+        options.add(&mut md);
+
+        let src = "[foo](ftp://example.com) [bar](http://example.com \"title\") [baz](/foo/bar)";
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<p><a href=\"ftp://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">foo</a> <a href=\"\" title=\"title\">bar</a> <a href=\"\">baz</a></p>\n"
+        );
+    }
+}
+
+#[test]
+fn test_url_scheme_check() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it::plugins::extra::add(&mut md);
+    add_url_scheme_check(&mut md);
+
+    let src = "[foo](ftp://example.com) [bar](https://example.com) <ssh://example.com>";
+    {
+        // without options, allowed links are left without target and rel
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<p>foo <a href=\"https://example.com\">bar</a> ssh://example.com</p>\n"
+        );
+    }
+
+    {
+        // with options
+        let options = MarkdonwItOptions::new(HashMap::from([
+            ("allowed_url_schemes".to_string(), OptionValue::from("ftp")),
+            (
+                "disallowed_link".to_string(),
+                OptionValue::from("empty_href"),
+            ),
+        ]))
+        .unwrap();
+        options.add(&mut md);
+
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<p><a href=\"ftp://example.com\">foo</a> <a href=\"\">bar</a> <a href=\"\">ssh://example.com</a></p>\n"
+        );
+    }
+
+    {
+        // links to files are annotated all the same
+        let options = MarkdonwItOptions::new(HashMap::from([(
+            "file_link_annotation".to_string(),
+            OptionValue::from(true),
+        )]))
+        .unwrap();
+        options.add(&mut md);
+
+        let html = md.parse("[hoge](/foo/bar/hoge.pdf) [top](/)").render();
+        assert_eq!(
+            html,
+            "<p><a href=\"/foo/bar/hoge.pdf\" class=\"file-link\">hoge（PDF）</a> <a href=\"/\">top</a></p>\n"
+        );
+    }
+}
+
+#[test]
+fn test_external_link_policy() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let src = "[foo](https://example.com) [bar](https://kyoto.dosue.jp)";
+    let render = |md: &mut MarkdownIt, options: Vec<(&str, OptionValue)>| {
+        let mut options: HashMap<String, OptionValue> = options
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        options.insert(
+            "internal_domain_name".to_string(),
+            OptionValue::from("kyoto.dosue.jp"),
+        );
+        MarkdonwItOptions::new(options).unwrap().add(md);
+        md.parse(src).render()
+    };
+
+    {
+        // rel for user generated contents
+        let html = render(
+            &mut md,
+            vec![(
+                "external_link_rel",
+                OptionValue::from("noopener nofollow ugc"),
+            )],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener nofollow ugc\">foo</a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+    }
+
+    {
+        // without rel
+        let html = render(
+            &mut md,
+            vec![("external_link_rel", OptionValue::from(false))],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\" target=\"_blank\">foo</a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+    }
+
+    {
+        // custom target
+        let html = render(
+            &mut md,
+            vec![("external_link_target", OptionValue::from("external"))],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\" target=\"external\" rel=\"noopener noreferrer\">foo</a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+    }
+
+    {
+        // not opened in new tabs
+        let html = render(
+            &mut md,
+            vec![("external_link_target", OptionValue::from(false))],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\">foo</a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+
+        // rel can still be given
+        let html = render(
+            &mut md,
+            vec![
+                ("external_link_target", OptionValue::from(false)),
+                ("external_link_rel", OptionValue::from("nofollow")),
+            ],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\" rel=\"nofollow\">foo</a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+    }
+}
+
+#[test]
+fn test_scheme_less_urls() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let src = "[foo](google.com/foo/bar) [bar](www.city.fuji.shizuoka.jp/foo) [baz](hoge.pdf)";
+    let mut render = |scheme_less_urls: &str| {
+        let options = MarkdonwItOptions::new(HashMap::from([
+            (
+                "internal_domain_name".to_string(),
+                OptionValue::from("city.fuji.shizuoka.jp"),
+            ),
+            (
+                "scheme_less_urls".to_string(),
+                OptionValue::from(scheme_less_urls),
+            ),
+        ]))
+        .unwrap();
+        options.add(&mut md);
+        md.parse(src).render()
+    };
+
+    // relative paths by default
+    assert_eq!(
+        render("internal"),
+        "<p><a href=\"google.com/foo/bar\">foo</a> <a href=\"www.city.fuji.shizuoka.jp/foo\">bar</a> <a href=\"hoge.pdf\">baz</a></p>\n"
+    );
+    assert_eq!(
+        render("external"),
+        "<p><a href=\"google.com/foo/bar\" target=\"_blank\" rel=\"noopener noreferrer\">foo</a> <a href=\"www.city.fuji.shizuoka.jp/foo\" target=\"_blank\" rel=\"noopener noreferrer\">bar</a> <a href=\"hoge.pdf\">baz</a></p>\n"
+    );
+    // internal domains are still recognized after `https://` is added
+    assert_eq!(
+        render("https"),
+        "<p><a href=\"https://google.com/foo/bar\" target=\"_blank\" rel=\"noopener noreferrer\">foo</a> <a href=\"https://www.city.fuji.shizuoka.jp/foo\">bar</a> <a href=\"hoge.pdf\">baz</a></p>\n"
+    );
+}
+
+#[test]
+fn test_new_window_hint() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    let src = "[foo](https://example.com) [bar](https://kyoto.dosue.jp)";
+    let render = |md: &mut MarkdownIt, options: Vec<(&str, OptionValue)>| {
+        let mut options: HashMap<String, OptionValue> = options
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        options.insert(
+            "internal_domain_name".to_string(),
+            OptionValue::from("kyoto.dosue.jp"),
+        );
+        MarkdonwItOptions::new(options).unwrap().add(md);
+        md.parse(src).render()
+    };
+
+    {
+        // visually hidden text
+        let html = render(
+            &mut md,
+            vec![("external_link_hint", OptionValue::from(true))],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">foo<span class=\"visually-hidden\">（新しいウィンドウで開きます）</span></a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+    }
+
+    {
+        // localized text and icon
+        let html = render(
+            &mut md,
+            vec![
+                ("external_link_hint", OptionValue::from(true)),
+                (
+                    "external_link_hint_text",
+                    OptionValue::from(" (opens in a new window)"),
+                ),
+                ("external_link_hint_class", OptionValue::from("sr-only")),
+                (
+                    "external_link_icon_class",
+                    OptionValue::from("icon-external"),
+                ),
+            ],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">foo<span class=\"sr-only\"> (opens in a new window)</span><span class=\"icon-external\" aria-hidden=\"true\"></span></a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+    }
+
+    {
+        // not for links opened in the same window
+        let html = render(
+            &mut md,
+            vec![
+                ("external_link_hint", OptionValue::from(true)),
+                (
+                    "external_link_icon_class",
+                    OptionValue::from("icon-external"),
+                ),
+                ("external_link_target", OptionValue::from(false)),
+            ],
+        );
+        assert_eq!(
+            html,
+            "<p><a href=\"https://example.com\">foo</a> <a href=\"https://kyoto.dosue.jp\">bar</a></p>\n"
+        );
+    }
+}
+
+#[test]
+fn test_file_link_annotation() {
+    use crate::driver::OptionValue;
+    use std::collections::{BTreeMap, HashMap};
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    add(&mut md);
+
+    MarkdonwItOptions::new(HashMap::from([
+        (
+            "internal_domain_name".to_string(),
+            OptionValue::from("kyoto.dosue.jp"),
+        ),
+        ("file_link_annotation".to_string(), OptionValue::from(true)),
+        (
+            "file_sizes".to_string(),
+            OptionValue::Map(BTreeMap::from([(
+                "/foo/bar/hoge.pdf".to_string(),
+                OptionValue::from(125_952),
+            )])),
+        ),
+    ]))
+    .unwrap()
+    .add(&mut md);
+
+    let html = md
+        .parse("[hoge](/foo/bar/hoge.pdf) [fuga](https://example.com/fuga.docx) [top](/)")
+        .render();
+    assert_eq!(
+        html,
+        "<p><a href=\"/foo/bar/hoge.pdf\" class=\"file-link\">hoge（PDF：123KB）</a> <a href=\"https://example.com/fuga.docx\" target=\"_blank\" rel=\"noopener noreferrer\" class=\"file-link\">fuga（DOCX）</a> <a href=\"/\">top</a></p>\n"
+    );
+
+    // the class is added to that of other plugins
+    let mut root = md.parse("[hoge](/foo/bar/hoge.pdf)");
+    root.walk_mut(|node, _| {
+        if node.is::<LinkWithTarget>() {
+            node.attrs.push(("class", "download".to_string()));
+        }
+    });
+    assert_eq!(
+        root.render(),
+        "<p><a class=\"download file-link\" href=\"/foo/bar/hoge.pdf\">hoge（PDF：123KB）</a></p>\n"
+    );
+}
@@ -0,0 +1,103 @@
+// detects urls written without scheme but starting with a hostname (e.g. `google.com/foo/bar`),
+// which are relative paths by definition, but are meant to be external sites in most cases
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+// top level domains vendored from the Public Suffix List (see the header of tlds.txt)
+const TLDS: &str = include_str!("tlds.txt");
+
+// top level domains also used as file extensions, to keep file names relative
+// (e.g. `README.md`, `archive.zip`, `setup.app`) unless they start with `www.`
+//
+// this is a heuristic, not exhaustive: file names of other extensions which happen to be
+// top level domains (e.g. `notes.page`) are still taken as hostnames
+const FILE_EXTENSIONS: [&str; 14] = [
+    "ai", "app", "cab", "cc", "java", "md", "mov", "pl", "pro", "ps", "py", "rs", "sh", "zip",
+];
+
+fn is_tld(label: &str) -> bool {
+    static TLD_SET: OnceLock<HashSet<&str>> = OnceLock::new();
+    TLD_SET
+        .get_or_init(|| {
+            TLDS.lines()
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .collect()
+        })
+        .contains(label)
+}
+
+// the hostname part of `url`, if it starts with one (e.g. `google.com` of `google.com/foo/bar`)
+// `url` is expected to have no scheme
+pub fn hostname(url: &str) -> Option<&str> {
+    if url.starts_with(['/', '.', '#', '?']) {
+        return None;
+    }
+    let authority = url.split(['/', '?', '#']).next()?;
+    let hostname = match authority.split_once(':') {
+        // with port number
+        Some((hostname, port)) if !port.is_empty() && port.chars().all(|c| c.is_ascii_digit()) => {
+            hostname
+        }
+        Some(_) => return None,
+        None => authority,
+    };
+
+    let labels: Vec<&str> = hostname.split('.').collect();
+    let is_valid_label = |label: &&str| {
+        !label.is_empty()
+            && !label.starts_with('-')
+            && !label.ends_with('-')
+            && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+    };
+    if labels.len() < 2 || !labels.iter().all(is_valid_label) {
+        return None;
+    }
+
+    let tld = labels.last()?.to_ascii_lowercase();
+    let is_file_extension =
+        FILE_EXTENSIONS.contains(&tld.as_str()) && !labels[0].eq_ignore_ascii_case("www");
+    if is_tld(&tld) && !is_file_extension {
+        Some(hostname)
+    } else {
+        None
+    }
+}
+
+#[test]
+fn test_hostname() {
+    assert_eq!(hostname("google.com/foo/bar"), Some("google.com"));
+    assert_eq!(hostname("google.com"), Some("google.com"));
+    assert_eq!(
+        hostname("www.city.fuji.shizuoka.jp?foo=bar"),
+        Some("www.city.fuji.shizuoka.jp")
+    );
+    assert_eq!(hostname("Example.COM#top"), Some("Example.COM"));
+    assert_eq!(hostname("example.in/foo"), Some("example.in"));
+    assert_eq!(hostname("example.so"), Some("example.so"));
+    assert_eq!(hostname("example.xn--tckwe"), Some("example.xn--tckwe"));
+    assert_eq!(
+        hostname("localhost.tokyo:3000/foo"),
+        Some("localhost.tokyo")
+    );
+    assert_eq!(hostname("www.example.app"), Some("www.example.app"));
+    assert_eq!(hostname("WWW.example.zip/foo"), Some("WWW.example.zip"));
+
+    // file names and paths
+    assert_eq!(hostname("foo.pdf"), None);
+    assert_eq!(hostname("README.md"), None);
+    assert_eq!(hostname("archive.zip"), None);
+    assert_eq!(hostname("setup.app"), None);
+    assert_eq!(hostname("report.pro"), None);
+    assert_eq!(hostname("MyApp.app/Contents/Info.plist"), None);
+    assert_eq!(hostname("foo.unknowntld"), None);
+    assert_eq!(hostname("hoge/google.com"), None);
+    assert_eq!(hostname("/google.com"), None);
+    assert_eq!(hostname("./google.com"), None);
+    assert_eq!(hostname("foo/bar"), None);
+    assert_eq!(hostname("1234"), None);
+    assert_eq!(hostname("google.com:foo"), None);
+    assert_eq!(hostname("-google.com"), None);
+
+    // file names of extensions not listed are taken as hostnames (a limit of the heuristic)
+    assert_eq!(hostname("notes.page"), Some("notes.page"));
+}
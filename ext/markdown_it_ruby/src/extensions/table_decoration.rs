@@ -1,7 +1,189 @@
+// classes and a caption of tables, optionally wrapped in a scrollable container for mobile
+//
+// e.g. `<div class="table-responsive"><table class="table"><caption>...</caption><thead class="...">...`
 use crate::driver::MarkdonwItOptions;
+use crate::extensions;
 use markdown_it::parser::core::CoreRule;
-use markdown_it::plugins::extra::tables::Table;
-use markdown_it::{MarkdownIt, Node};
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::plugins::extra::tables::{Table, TableBody, TableCell, TableHead, TableRow};
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+// the extension is added when any of them is given (besides `table_class_name`, enabled by default)
+pub const OPTIONS: [&str; 8] = [
+    "table_wrapper_class",
+    "table_head_class",
+    "table_body_class",
+    "table_row_class",
+    "table_header_cell_class",
+    "table_cell_class",
+    "table_captions",
+    "table_caption_prefix",
+];
+
+// `<div class="table-responsive">`, which makes wide tables scrollable
+#[derive(Debug)]
+pub struct TableWrapper {
+    pub class: String,
+}
+
+impl NodeValue for TableWrapper {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        fmt.cr();
+        fmt.open("div", &[("class", self.class.clone())]);
+        fmt.contents(&node.children);
+        fmt.close("div");
+        fmt.cr();
+    }
+}
+
+// taken from a paragraph preceding the table (e.g. `Table: 令和6年度の予算`)
+#[derive(Debug)]
+pub struct TableCaption;
+
+impl NodeValue for TableCaption {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        fmt.open("caption", &node.attrs);
+        fmt.contents(&node.children);
+        fmt.close("caption");
+        fmt.cr();
+    }
+}
+
+#[derive(Debug)]
+struct TableDecoration {
+    // `None` with `table_class_name: false`
+    table_class: Option<String>,
+    wrapper_class: Option<String>,
+    head_class: Option<String>,
+    body_class: Option<String>,
+    row_class: Option<String>,
+    header_cell_class: Option<String>,
+    cell_class: Option<String>,
+    // e.g. `Table:`, `None` unless `table_captions: true` is given
+    caption_prefix: Option<String>,
+}
+
+impl TableDecoration {
+    fn new(options: Option<&MarkdonwItOptions>) -> Self {
+        let default_options = MarkdonwItOptions::default();
+        let options = options.unwrap_or(&default_options);
+        let class = |key: &str| options.get_string(key);
+
+        Self {
+            table_class: options
+                .is_enabled("table_class_name", true)
+                .then(|| options.get_option_or_default("table_class_name", "table")),
+            wrapper_class: class("table_wrapper_class"),
+            head_class: class("table_head_class"),
+            body_class: class("table_body_class"),
+            row_class: class("table_row_class"),
+            header_cell_class: class("table_header_cell_class"),
+            cell_class: class("table_cell_class"),
+            caption_prefix: options
+                .is_enabled("table_captions", false)
+                .then(|| options.get_option_or_default("table_caption_prefix", "Table:")),
+        }
+    }
+
+    fn add_class(node: &mut Node, class: &Option<String>) {
+        if let Some(class) = class {
+            extensions::add_class(&mut node.attrs, class);
+        }
+    }
+
+    fn decorate(&self, table: &mut Node) {
+        Self::add_class(table, &self.table_class);
+        for section in table.children.iter_mut() {
+            let (section_class, cell_class) = if section.is::<TableHead>() {
+                (&self.head_class, &self.header_cell_class)
+            } else if section.is::<TableBody>() {
+                (&self.body_class, &self.cell_class)
+            } else {
+                continue;
+            };
+            Self::add_class(section, section_class);
+
+            for row in section
+                .children
+                .iter_mut()
+                .filter(|row| row.is::<TableRow>())
+            {
+                Self::add_class(row, &self.row_class);
+                for cell in row
+                    .children
+                    .iter_mut()
+                    .filter(|cell| cell.is::<TableCell>())
+                {
+                    Self::add_class(cell, cell_class);
+                }
+            }
+        }
+    }
+
+    // a paragraph beginning with the prefix (e.g. `Table: 令和6年度の予算`)
+    fn is_caption(&self, node: &Node) -> bool {
+        let Some(prefix) = &self.caption_prefix else {
+            return false;
+        };
+        node.is::<Paragraph>()
+            && node
+                .children
+                .first()
+                .and_then(|child| child.cast::<Text>())
+                .is_some_and(|text| text.content.starts_with(prefix.as_str()))
+    }
+
+    // the caption paragraph turned into a caption, without the prefix
+    fn caption(&self, mut paragraph: Node) -> Node {
+        let prefix = self.caption_prefix.as_deref().unwrap_or_default();
+        let mut caption = Node::new(TableCaption);
+        caption.srcmap = paragraph.srcmap;
+        caption.children = std::mem::take(&mut paragraph.children);
+
+        if let Some(text) = caption.children[0].cast_mut::<Text>() {
+            text.content = text.content[prefix.len()..].trim_start().to_string();
+            if text.content.is_empty() {
+                caption.children.remove(0);
+            }
+        }
+        caption
+    }
+
+    // tables among the children of the node
+    fn run(&self, node: &mut Node) {
+        let mut index = 0;
+        while index < node.children.len() {
+            if !node.children[index].is::<Table>() {
+                index += 1;
+                continue;
+            }
+
+            // the caption paragraph is moved into the table
+            if index > 0 && self.is_caption(&node.children[index - 1]) {
+                let paragraph = node.children.remove(index - 1);
+                index -= 1;
+                let caption = self.caption(paragraph);
+                node.children[index].children.insert(0, caption);
+            }
+
+            let table = &mut node.children[index];
+            self.decorate(table);
+            if let Some(class) = &self.wrapper_class {
+                let table = std::mem::replace(
+                    table,
+                    Node::new(TableWrapper {
+                        class: class.clone(),
+                    }),
+                );
+                let wrapper = &mut node.children[index];
+                wrapper.srcmap = table.srcmap;
+                wrapper.children.push(table);
+            }
+            index += 1;
+        }
+    }
+}
 
 #[derive(Debug)]
 struct TableDecorationRule;
@@ -9,17 +191,11 @@ struct TableDecorationRule;
 impl CoreRule for TableDecorationRule {
     // a custom function that will be invoked once per document.
     fn run(root: &mut Node, md: &MarkdownIt) {
-        let options = md.ext.get::<MarkdonwItOptions>();
-        let table_class_name = match options {
-            Some(options) => options.get_option_or_default("table_class_name", "table"),
-            None => "table".to_string(),
-        };
+        let table_decoration = TableDecoration::new(md.ext.get::<MarkdonwItOptions>());
         root.walk_mut(|node, _| {
-            if let Some(table) = node.cast::<Table>() {
-                node.replace(Table {
-                    alignments: table.alignments.clone(),
-                });
-                node.attrs = vec![("class", table_class_name.clone())];
+            // tables are handled by their parents, not to be wrapped again
+            if !node.is::<TableWrapper>() {
+                table_decoration.run(node);
             }
         });
     }
@@ -31,6 +207,7 @@ pub fn add(md: &mut MarkdownIt) {
 
 #[test]
 fn test_table_decoration() {
+    use crate::driver::OptionValue;
     use std::collections::HashMap;
 
     let mut md = MarkdownIt::new();
@@ -54,8 +231,9 @@ fn test_table_decoration() {
         // with options
         let options = MarkdonwItOptions::new(HashMap::from([(
             "table_class_name".to_string(),
-            "custom-table-class-name".to_string(),
-        )]));
+            OptionValue::from("custom-table-class-name"),
+        )]))
+        .unwrap();
         options.add(&mut md);
 
         let src = "| 左寄せタイトル | センタリング | 右寄せタイトル |\n |:------------|:------------:|-----------:|\n | column | column | column |\n";
@@ -67,3 +245,74 @@ fn test_table_decoration() {
         )
     }
 }
+
+#[test]
+fn test_table_decoration_elements() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+
+    markdown_it::plugins::cmark::add(&mut md);
+    markdown_it::plugins::extra::add(&mut md);
+    add(&mut md);
+
+    MarkdonwItOptions::new(HashMap::from([
+        (
+            "table_wrapper_class".to_string(),
+            OptionValue::from("table-responsive"),
+        ),
+        (
+            "table_head_class".to_string(),
+            OptionValue::from("thead-light"),
+        ),
+        ("table_body_class".to_string(), OptionValue::from("tbody")),
+        ("table_row_class".to_string(), OptionValue::from("row")),
+        (
+            "table_header_cell_class".to_string(),
+            OptionValue::from("header-cell"),
+        ),
+        ("table_cell_class".to_string(), OptionValue::from("cell")),
+        ("table_captions".to_string(), OptionValue::from(true)),
+    ]))
+    .unwrap()
+    .add(&mut md);
+
+    {
+        // caption without a blank line
+        let src = "Table: 令和6年度の*予算*\n| 項目 | 金額 |\n|:--|--:|\n| 教育 | 100 |\n";
+        let html = md.parse(src).render();
+
+        assert_eq!(
+            html,
+            "<div class=\"table-responsive\">\n<table class=\"table\">\n<caption>令和6年度の<em>予算</em></caption>\n<thead class=\"thead-light\">\n<tr class=\"row\">\n<th class=\"header-cell\" style=\"text-align:left\">項目</th>\n<th class=\"header-cell\" style=\"text-align:right\">金額</th>\n</tr>\n</thead>\n<tbody class=\"tbody\">\n<tr class=\"row\">\n<td class=\"cell\" style=\"text-align:left\">教育</td>\n<td class=\"cell\" style=\"text-align:right\">100</td>\n</tr>\n</tbody>\n</table>\n</div>\n"
+        )
+    }
+
+    {
+        // caption after a blank line, and in a blockquote
+        let src = "> Table: 予算\n>\n> | 項目 |\n> |---|\n> | 教育 |\n\nTable:\n";
+        let html = md.parse(src).render();
+
+        assert_eq!(
+            html,
+            "<blockquote>\n<div class=\"table-responsive\">\n<table class=\"table\">\n<caption>予算</caption>\n<thead class=\"thead-light\">\n<tr class=\"row\">\n<th class=\"header-cell\">項目</th>\n</tr>\n</thead>\n<tbody class=\"tbody\">\n<tr class=\"row\">\n<td class=\"cell\">教育</td>\n</tr>\n</tbody>\n</table>\n</div>\n</blockquote>\n<p>Table:</p>\n"
+        )
+    }
+
+    {
+        // existing attributes are kept
+        let mut node = Node::new(TableCaption);
+        node.attrs.push(("id", "budget".to_string()));
+        node.attrs.push(("class", "striped".to_string()));
+        TableDecoration::add_class(&mut node, &Some("table".to_string()));
+
+        assert_eq!(
+            node.attrs,
+            vec![
+                ("id", "budget".to_string()),
+                ("class", "striped table".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,243 @@
+use crate::driver::MarkdonwItOptions;
+use crate::extensions::heading_anchor::HeadingAnchorRule;
+use markdown_it::parser::core::CoreRule;
+use markdown_it::parser::extset::NodeExt;
+use markdown_it::parser::inline::Text;
+use markdown_it::plugins::cmark::block::heading::ATXHeading;
+use markdown_it::plugins::cmark::block::lheading::SetextHeader;
+use markdown_it::plugins::cmark::block::paragraph::Paragraph;
+use markdown_it::{MarkdownIt, Node, NodeValue, Renderer};
+
+// placeholders replaced with the table of contents (case insensitive)
+const PLACEHOLDERS: [&str; 2] = ["[[toc]]", "[toc]"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct TocEntry {
+    pub level: u8,
+    pub id: String,
+    pub text: String,
+    pub children: Vec<TocEntry>,
+}
+
+// headings of the document, nested by their levels (stored in `root.ext`)
+#[derive(Debug, Default, Clone)]
+pub struct TableOfContents {
+    pub entries: Vec<TocEntry>,
+}
+
+impl NodeExt for TableOfContents {}
+
+impl TableOfContents {
+    // a heading becomes a child of the nearest preceding heading with a smaller level
+    fn push(entries: &mut Vec<TocEntry>, entry: TocEntry) {
+        if let Some(last) = entries.last_mut() {
+            if entry.level > last.level {
+                Self::push(&mut last.children, entry);
+                return;
+            }
+        }
+        entries.push(entry);
+    }
+}
+
+// rendered in place of `[[toc]]` / `[TOC]`
+#[derive(Debug)]
+pub struct TableOfContentsNode {
+    pub entries: Vec<TocEntry>,
+}
+
+impl NodeValue for TableOfContentsNode {
+    fn render(&self, node: &Node, fmt: &mut dyn Renderer) {
+        fmt.cr();
+        fmt.open("nav", &node.attrs);
+        Self::render_entries(&self.entries, fmt);
+        fmt.close("nav");
+        fmt.cr();
+    }
+}
+
+impl TableOfContentsNode {
+    fn render_entries(entries: &[TocEntry], fmt: &mut dyn Renderer) {
+        if entries.is_empty() {
+            return;
+        }
+
+        fmt.cr();
+        fmt.open("ul", &[]);
+        fmt.cr();
+        for entry in entries {
+            fmt.open("li", &[]);
+            fmt.open("a", &[("href", format!("#{}", entry.id))]);
+            fmt.text(&entry.text);
+            fmt.close("a");
+            Self::render_entries(&entry.children, fmt);
+            fmt.close("li");
+            fmt.cr();
+        }
+        fmt.close("ul");
+        fmt.cr();
+    }
+}
+
+struct TableOfContentsRule;
+
+impl CoreRule for TableOfContentsRule {
+    // a custom function that will be invoked once per document.
+    fn run(root: &mut Node, md: &MarkdownIt) {
+        let default_options = MarkdonwItOptions::default();
+        let options = md
+            .ext
+            .get::<MarkdonwItOptions>()
+            .unwrap_or(&default_options);
+        let min_level = Self::level_option(options, "toc_min_level", 1);
+        let max_level = Self::level_option(options, "toc_max_level", 6);
+        let toc_class = options.get_option_or_default("toc_class", "table-of-contents");
+
+        // headings are collected after their levels are modified by `heading_level_offset`
+        let mut toc = TableOfContents::default();
+        root.walk(|node, _| {
+            let level = if let Some(heading) = node.cast::<ATXHeading>() {
+                heading.level
+            } else if let Some(heading) = node.cast::<SetextHeader>() {
+                heading.level
+            } else {
+                return;
+            };
+            if level < min_level || level > max_level {
+                return;
+            }
+            let Some((_, id)) = node.attrs.iter().find(|(key, _)| *key == "id") else {
+                return;
+            };
+
+            let entry = TocEntry {
+                level,
+                id: id.clone(),
+                text: node.collect_text(),
+                children: Vec::new(),
+            };
+            TableOfContents::push(&mut toc.entries, entry);
+        });
+
+        root.walk_mut(|node, _| {
+            if Self::is_placeholder(node) {
+                let mut toc_node = Node::new(TableOfContentsNode {
+                    entries: toc.entries.clone(),
+                });
+                toc_node.srcmap = node.srcmap;
+                toc_node.attrs = vec![("class", toc_class.clone())];
+                *node = toc_node;
+            }
+        });
+
+        root.ext.insert(toc);
+    }
+}
+
+impl TableOfContentsRule {
+    fn level_option(options: &MarkdonwItOptions, key: &str, default: u8) -> u8 {
+        // validated to be within 1-6
+        options.get_integer_or_default(key, default.into()) as u8
+    }
+
+    // a paragraph consisting of the placeholder only
+    fn is_placeholder(node: &Node) -> bool {
+        if !node.is::<Paragraph>() || !node.children.iter().all(|child| child.is::<Text>()) {
+            return false;
+        }
+        let text = node.collect_text().trim().to_ascii_lowercase();
+        PLACEHOLDERS.contains(&text.as_str())
+    }
+}
+
+pub fn add(md: &mut MarkdownIt) {
+    // entries refer to the ids given by `HeadingAnchorRule`
+    md.add_rule::<TableOfContentsRule>()
+        .after::<HeadingAnchorRule>()
+        .after_all();
+}
+
+#[test]
+fn test_table_of_contents() {
+    use crate::driver::OptionValue;
+    use std::collections::HashMap;
+
+    let mut md = MarkdownIt::new();
+    markdown_it::plugins::cmark::add(&mut md);
+    crate::extensions::heading_anchor::add(&mut md);
+    add(&mut md);
+
+    {
+        // without options
+        let src =
+            "# Title\n\n[[toc]]\n\n## Section 1\n### Section 1-1\n## Section *2*\n#### Section 2-1";
+        let root = md.parse(src);
+
+        let toc = root.ext.get::<TableOfContents>().unwrap();
+        assert_eq!(
+            toc.entries,
+            vec![TocEntry {
+                level: 1,
+                id: "title".to_string(),
+                text: "Title".to_string(),
+                children: vec![
+                    TocEntry {
+                        level: 2,
+                        id: "section-1".to_string(),
+                        text: "Section 1".to_string(),
+                        children: vec![TocEntry {
+                            level: 3,
+                            id: "section-1-1".to_string(),
+                            text: "Section 1-1".to_string(),
+                            children: vec![],
+                        }],
+                    },
+                    TocEntry {
+                        level: 2,
+                        id: "section-2".to_string(),
+                        text: "Section 2".to_string(),
+                        children: vec![TocEntry {
+                            level: 4,
+                            id: "section-2-1".to_string(),
+                            text: "Section 2-1".to_string(),
+                            children: vec![],
+                        }],
+                    },
+                ],
+            }]
+        );
+
+        assert_eq!(
+            root.render(),
+            "<h1 id=\"title\">Title</h1>\n<nav class=\"table-of-contents\">\n<ul>\n<li><a href=\"#title\">Title</a>\n<ul>\n<li><a href=\"#section-1\">Section 1</a>\n<ul>\n<li><a href=\"#section-1-1\">Section 1-1</a></li>\n</ul>\n</li>\n<li><a href=\"#section-2\">Section 2</a>\n<ul>\n<li><a href=\"#section-2-1\">Section 2-1</a></li>\n</ul>\n</li>\n</ul>\n</li>\n</ul>\n</nav>\n<h2 id=\"section-1\">Section 1</h2>\n<h3 id=\"section-1-1\">Section 1-1</h3>\n<h2 id=\"section-2\">Section <em>2</em></h2>\n<h4 id=\"section-2-1\">Section 2-1</h4>\n"
+        );
+    }
+
+    {
+        // `[TOC]` placeholder, and paragraphs containing other contents are kept as is
+        let src = "[TOC]\n\nsee [[toc]]\n\n## Section";
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<nav class=\"table-of-contents\">\n<ul>\n<li><a href=\"#section\">Section</a></li>\n</ul>\n</nav>\n<p>see [[toc]]</p>\n<h2 id=\"section\">Section</h2>\n"
+        );
+    }
+
+    {
+        // with options
+        let options = MarkdonwItOptions::new(HashMap::from([
+            ("toc_min_level".to_string(), OptionValue::from(2)),
+            ("toc_max_level".to_string(), OptionValue::from(3)),
+            ("toc_class".to_string(), OptionValue::from("toc")),
+        ]))
+        .unwrap();
+        options.add(&mut md);
+
+        let src = "# Title\n\n[[TOC]]\n\n## Section\n### Section\n#### Section";
+        let html = md.parse(src).render();
+        assert_eq!(
+            html,
+            "<h1 id=\"title\">Title</h1>\n<nav class=\"toc\">\n<ul>\n<li><a href=\"#section\">Section</a>\n<ul>\n<li><a href=\"#section-1\">Section</a></li>\n</ul>\n</li>\n</ul>\n</nav>\n<h2 id=\"section\">Section</h2>\n<h3 id=\"section-1\">Section</h3>\n<h4 id=\"section-2\">Section</h4>\n"
+        );
+    }
+}
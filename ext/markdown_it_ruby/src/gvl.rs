@@ -0,0 +1,51 @@
+use rb_sys::rb_thread_call_without_gvl;
+use std::ffi::c_void;
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+// runs `func` without holding the GVL, so that other Ruby threads (e.g. of Puma or Sidekiq)
+// can run in the meantime
+//
+// `func` must not touch any Ruby objects, nor call Ruby APIs.
+// it cannot be interrupted (e.g. by `Thread#raise`) until it returns.
+pub(crate) fn without_gvl<F, R>(func: F) -> R
+where
+    F: FnOnce() -> R,
+{
+    struct Call<F, R> {
+        func: Option<F>,
+        result: Option<std::thread::Result<R>>,
+    }
+
+    unsafe extern "C" fn trampoline<F, R>(data: *mut c_void) -> *mut c_void
+    where
+        F: FnOnce() -> R,
+    {
+        let call = &mut *(data as *mut Call<F, R>);
+        if let Some(func) = call.func.take() {
+            // panics must not unwind across the C stack of Ruby
+            call.result = Some(panic::catch_unwind(AssertUnwindSafe(func)));
+        }
+        ptr::null_mut()
+    }
+
+    let mut call = Call {
+        func: Some(func),
+        result: None,
+    };
+    unsafe {
+        rb_thread_call_without_gvl(
+            Some(trampoline::<F, R>),
+            &mut call as *mut Call<F, R> as *mut c_void,
+            None,
+            ptr::null_mut(),
+        );
+    }
+
+    match call.result {
+        Some(Ok(result)) => result,
+        // re-raised with the GVL (magnus turns panics into Ruby exceptions)
+        Some(Err(payload)) => panic::resume_unwind(payload),
+        None => unreachable!("the function is always called"),
+    }
+}
@@ -1,7 +1,14 @@
+mod document;
 mod driver;
 mod extensions;
-use magnus::{define_module, function, prelude::*, Error};
-use std::collections::HashMap;
+mod gvl;
+use magnus::{
+    class, define_module, function, method, prelude::*, r_hash::ForEach, value::Lazy, Error,
+    ExceptionClass, Integer, RArray, RHash, RString, Ruby, Symbol, Value,
+};
+use rayon::prelude::*;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
 
 // macro for regex
 //
@@ -18,18 +25,203 @@ macro_rules! regex {
     }};
 }
 
-use driver::MarkdownDriver;
+use document::{Document, DocumentNode};
+use driver::{ConversionError, LookupUrls, MarkdownDriver, OptionValue, OutputFormat};
+use gvl::without_gvl;
 
-fn convert(contents: String, options: HashMap<String, String>) -> String {
-    let handler = MarkdownDriver::new(options);
-    handler.parse(contents);
-    handler.render()
+// `MarkdownIt::Error`, raised when a document cannot be converted
+static ERROR: Lazy<ExceptionClass> = Lazy::new(|ruby| {
+    ruby.define_module("MarkdownIt")
+        .and_then(|module| module.define_error("Error", ruby.exception_standard_error()))
+        .unwrap()
+});
+
+fn conversion_error(ruby: &Ruby, error: impl fmt::Display) -> Error {
+    Error::new(ruby.get_inner(&ERROR), error.to_string())
+}
+
+// options given from Ruby (keys are stringified on the Ruby side)
+// values keep their types, so that they can be validated by the driver
+fn driver_options(ruby: &Ruby, options: RHash) -> Result<HashMap<String, OptionValue>, Error> {
+    let mut env = HashMap::new();
+    options.foreach(|key: String, value: Value| {
+        // `nil` is treated as if the option is not given
+        if !value.is_nil() {
+            let value = option_value(ruby, &key, value)?;
+            env.insert(key, value);
+        }
+        Ok(ForEach::Continue)
+    })?;
+    Ok(env)
+}
+
+fn option_value(ruby: &Ruby, key: &str, value: Value) -> Result<OptionValue, Error> {
+    if value.is_kind_of(ruby.class_true_class()) {
+        Ok(OptionValue::Bool(true))
+    } else if value.is_kind_of(ruby.class_false_class()) {
+        Ok(OptionValue::Bool(false))
+    } else if let Some(integer) = Integer::from_value(value) {
+        Ok(OptionValue::Integer(integer.to_i64()?))
+    } else if let Some(string) = RString::from_value(value) {
+        Ok(OptionValue::String(string.to_string()?))
+    } else if let Some(symbol) = Symbol::from_value(value) {
+        Ok(OptionValue::String(symbol.name()?.to_string()))
+    } else if let Some(array) = RArray::from_value(value) {
+        array
+            .to_vec::<Value>()?
+            .into_iter()
+            .map(|item| option_value(ruby, key, item))
+            .collect::<Result<Vec<_>, _>>()
+            .map(OptionValue::List)
+    } else if let Some(hash) = RHash::from_value(value) {
+        // e.g. `file_sizes: { "/foo/bar/hoge.pdf" => 125952 }`
+        let mut values = BTreeMap::new();
+        hash.foreach(|item_key: Value, item: Value| {
+            match option_value(ruby, key, item_key)? {
+                OptionValue::String(item_key) => {
+                    values.insert(item_key, option_value(ruby, key, item)?);
+                }
+                _ => {
+                    return Err(conversion_error(
+                        ruby,
+                        format!("invalid value for {}: {}", key, value.inspect()),
+                    ))
+                }
+            }
+            Ok(ForEach::Continue)
+        })?;
+        Ok(OptionValue::Map(values))
+    } else {
+        Err(conversion_error(
+            ruby,
+            format!("invalid value for {}: {}", key, value.inspect()),
+        ))
+    }
+}
+
+// a parser configured once on the Ruby side (`MarkdownIt::Parser.new(**options)`),
+// which can convert many documents without rebuilding MarkdownIt
+#[magnus::wrap(class = "MarkdownIt::Parser", free_immediately, size)]
+struct Parser {
+    driver: MarkdownDriver,
+}
+
+impl Parser {
+    fn new(ruby: &Ruby, options: RHash) -> Result<Self, Error> {
+        let driver = MarkdownDriver::new(driver_options(ruby, options)?)
+            .map_err(|error| conversion_error(ruby, error))?;
+        Ok(Self { driver })
+    }
+
+    // parsing and rendering run without the GVL (only Rust values are used there)
+    fn convert(ruby: &Ruby, rb_self: &Self, contents: String) -> Result<String, Error> {
+        without_gvl(|| rb_self.driver.convert(&contents, OutputFormat::Html))
+            .map_err(|error| conversion_error(ruby, error))
+    }
+
+    // `[html, warnings]`, e.g. warnings of images without alt text
+    fn convert_with_warnings(
+        ruby: &Ruby,
+        rb_self: &Self,
+        contents: String,
+    ) -> Result<(String, Vec<String>), Error> {
+        without_gvl(|| {
+            rb_self
+                .driver
+                .convert_with_warnings(&contents, OutputFormat::Html)
+        })
+        .map_err(|error| conversion_error(ruby, error))
+    }
+
+    fn to_mdast(ruby: &Ruby, rb_self: &Self, contents: String) -> Result<String, Error> {
+        without_gvl(|| rb_self.driver.convert(&contents, OutputFormat::Mdast))
+            .map_err(|error| conversion_error(ruby, error))
+    }
+
+    fn parse(ruby: &Ruby, rb_self: &Self, contents: String) -> Result<Document, Error> {
+        without_gvl(|| rb_self.driver.parse_document(&contents))
+            .map(Document::new)
+            .map_err(|error| conversion_error(ruby, error))
+    }
+
+    // urls which `file_sizes` and `image_dimensions` callbacks are called with (by option names),
+    // collected from the documents in parallel without the GVL (see `MarkdownIt.resolve_lookups`)
+    fn lookup_urls(
+        ruby: &Ruby,
+        rb_self: &Self,
+        contents: Vec<String>,
+    ) -> Result<HashMap<&'static str, Vec<String>>, Error> {
+        let results: Vec<Result<LookupUrls, ConversionError>> = without_gvl(|| {
+            contents
+                .par_iter()
+                .map(|contents| rb_self.driver.lookup_urls(contents))
+                .collect()
+        });
+        let documents =
+            ConversionError::collect(results).map_err(|error| conversion_error(ruby, error))?;
+
+        let (mut file_sizes, mut image_dimensions) = (Vec::new(), Vec::new());
+        for document in documents {
+            file_sizes.extend(document.file_sizes);
+            image_dimensions.extend(document.image_dimensions);
+        }
+        Ok(HashMap::from([
+            ("file_sizes", file_sizes),
+            ("image_dimensions", image_dimensions),
+        ]))
+    }
+}
+
+fn convert(ruby: &Ruby, contents: String, options: RHash) -> Result<String, Error> {
+    let handler = MarkdownDriver::new(driver_options(ruby, options)?)
+        .map_err(|error| conversion_error(ruby, error))?;
+    without_gvl(|| handler.convert(&contents, OutputFormat::Html))
+        .map_err(|error| conversion_error(ruby, error))
+}
+
+// converts documents in parallel on a thread pool, with the options applied once
+fn convert_many(ruby: &Ruby, contents: Vec<String>, options: RHash) -> Result<Vec<String>, Error> {
+    let handler = MarkdownDriver::new(driver_options(ruby, options)?)
+        .map_err(|error| conversion_error(ruby, error))?;
+    let results: Vec<Result<String, ConversionError>> = without_gvl(|| {
+        contents
+            .par_iter()
+            .map(|contents| handler.convert(contents, OutputFormat::Html))
+            .collect()
+    });
+    ConversionError::collect(results).map_err(|error| conversion_error(ruby, error))
 }
 
 #[magnus::init]
-fn init() -> Result<(), Error> {
+fn init(ruby: &Ruby) -> Result<(), Error> {
     let module = define_module("MarkdownIt")?;
+    Lazy::force(&ERROR, ruby);
     module.define_singleton_method("__convert", function!(convert, 2))?;
+    module.define_singleton_method("__convert_many", function!(convert_many, 2))?;
+
+    let parser = module.define_class("Parser", class::object())?;
+    parser.define_singleton_method("__new", function!(Parser::new, 1))?;
+    parser.define_method("convert", method!(Parser::convert, 1))?;
+    parser.define_method(
+        "convert_with_warnings",
+        method!(Parser::convert_with_warnings, 1),
+    )?;
+    parser.define_method("to_mdast", method!(Parser::to_mdast, 1))?;
+    parser.define_method("parse", method!(Parser::parse, 1))?;
+    parser.define_method("__lookup_urls", method!(Parser::lookup_urls, 1))?;
+
+    let document = module.define_class("Document", class::object())?;
+    document.define_method("root", method!(Document::root, 0))?;
+    document.define_method("children", method!(Document::children, 0))?;
+    document.define_method("toc", method!(Document::toc, 0))?;
+    document.define_method("warnings", method!(Document::warnings, 0))?;
+
+    let node = module.define_class("Node", class::object())?;
+    node.define_method("type", method!(DocumentNode::node_type, 0))?;
+    node.define_method("attributes", method!(DocumentNode::attributes, 0))?;
+    node.define_method("children", method!(DocumentNode::children, 0))?;
+    node.define_method("source_position", method!(DocumentNode::source_position, 0))?;
+    node.define_method("text", method!(DocumentNode::text, 0))?;
 
     Ok(())
 }
@@ -8,8 +8,121 @@ module MarkdownIt
 
   def self.convert(input, **options)
     options.transform_keys!(&:to_s)
-    options.transform_values!(&:to_s)
 
-    __convert(input, options)
+    __convert(input, resolve_lookups([input], options))
+  end
+
+  # converts many documents at once with the same options, in parallel on native threads
+  #
+  #   MarkdownIt.convert_many(["# title 1", "# title 2"]) # => ["<h1>title 1</h1>\n", "<h1>title 2</h1>\n"]
+  def self.convert_many(inputs, **options)
+    options.transform_keys!(&:to_s)
+
+    __convert_many(inputs, resolve_lookups(inputs, options))
+  end
+
+  # converts markdown into html, along with warnings found in the document
+  #
+  #   MarkdownIt.convert_with_warnings("![](a.png)", missing_alt_text: "warning")
+  #   # => ["<p><img src=\"a.png\" alt=\"\"></p>\n", ["line 1: image without alt text (a.png)"]]
+  def self.convert_with_warnings(input, **options)
+    options.transform_keys!(&:to_s)
+
+    Parser.new(**resolve_lookups([input], options)).convert_with_warnings(input)
+  end
+
+  # converts markdown into JSON following the mdast schema (https://github.com/syntax-tree/mdast)
+  #
+  #   MarkdownIt.to_mdast("# title") # => "{\"children\":[{\"children\":[...],\"depth\":1,...,\"type\":\"heading\"}],...}"
+  def self.to_mdast(input, **options)
+    options.transform_keys!(&:to_s)
+
+    Parser.new(**resolve_lookups([input], options)).to_mdast(input)
+  end
+
+  # parses markdown into a walkable node tree
+  #
+  #   document = MarkdownIt.parse("# title\n[link](https://example.com)")
+  #   document.map(&:type) # => ["Root", "ATXHeading", "Text", "Paragraph", "LinkWithTarget", "Text"]
+  def self.parse(input, **options)
+    options.transform_keys!(&:to_s)
+
+    Parser.new(**resolve_lookups([input], options)).parse(input)
+  end
+
+  # options which may be given as callbacks, called with the urls of file links and images respectively
+  LOOKUP_OPTIONS = %w[file_sizes image_dimensions].freeze
+  private_constant :LOOKUP_OPTIONS
+
+  # callbacks are turned into hashes of url => value beforehand,
+  # since documents are converted without the GVL (where Ruby code cannot be called)
+  #
+  #   MarkdownIt.convert("[hoge](/files/hoge.pdf)", file_link_annotation: true,
+  #                      file_sizes: ->(path) { File.size?(File.join("public", path)) })
+  #   # => "<p><a href=\"/files/hoge.pdf\" class=\"file-link\">hoge（PDF：123KB）</a></p>\n"
+  #
+  # note that a callback costs another parser and a second parse of every document on every call
+  # (without the GVL, in parallel) to collect the urls; `MarkdownIt::Parser` takes hashes only
+  def self.resolve_lookups(inputs, options)
+    callbacks = options.slice(*LOOKUP_OPTIONS).select { |_, lookup| lookup.respond_to?(:call) }
+    return options if callbacks.empty?
+
+    # urls are looked up as written, not resolved against `base_url` nor rewritten for the image proxy
+    disabled = [*LOOKUP_OPTIONS, "base_url", "asset_base_url", "image_proxy_url"].to_h { |key| [key, nil] }
+    urls = Parser.new(**options.merge(disabled)).__lookup_urls(inputs)
+    resolved = callbacks.to_h do |key, lookup|
+      [key, urls[key].uniq.to_h { |url| [url, lookup.call(url)] }.compact]
+    end
+
+    options.merge(resolved)
+  end
+  private_class_method :resolve_lookups
+
+  # a parser configured once, which can convert many documents
+  #
+  #   parser = MarkdownIt::Parser.new(heading_level_offset: 1)
+  #   parser.convert("# title") # => "<h2>title</h2>\n"
+  #
+  # `file_sizes` and `image_dimensions` must be hashes of url => value here, since callbacks
+  # are resolved against the documents to convert (raises MarkdownIt::Error otherwise)
+  class Parser
+    def self.new(**options)
+      options.transform_keys!(&:to_s)
+      callback = LOOKUP_OPTIONS.find { |key| options[key].respond_to?(:call) }
+      if callback
+        raise Error, "invalid value for #{callback}: callbacks are not supported by MarkdownIt::Parser, give a hash instead"
+      end
+
+      __new(options)
+    end
+  end
+
+  class Document
+    include Enumerable
+
+    # walks through all the nodes in the document (depth-first), starting from the root node
+    def each(&block)
+      return enum_for(:each) unless block
+
+      root.each(&block)
+      self
+    end
+  end
+
+  class Node
+    include Enumerable
+
+    # walks through the node itself and all its descendants (depth-first)
+    def each(&block)
+      return enum_for(:each) unless block
+
+      yield self
+      children.each { |child| child.each(&block) }
+      self
+    end
+
+    def inspect
+      "#<#{self.class.name} type=#{type} attributes=#{attributes.inspect} text=#{text.inspect}>"
+    end
   end
 end
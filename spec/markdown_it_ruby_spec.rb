@@ -57,5 +57,661 @@ RSpec.describe MarkdownIt do
         end
       end
     end
+
+    context 'with html option' do
+      subject { described_class.convert(input, html: html) }
+
+      let(:input) { "<div onclick=\"alert(1)\">text</div>\n\n<script>alert(1)</script>\n\nsome <b>bold</b> text" }
+
+      context 'when allowed' do
+        let(:html) { 'allow' }
+
+        it 'outputs raw html as is' do
+          expect(subject).to eq(
+            "<div onclick=\"alert(1)\">text</div>\n<script>alert(1)</script>\n<p>some <b>bold</b> text</p>\n",
+          )
+        end
+      end
+
+      context 'when escaped' do
+        let(:html) { 'escape' }
+
+        it 'outputs raw html as text' do
+          expect(subject).to eq(
+            "<p>&lt;div onclick=“alert(1)”&gt;text&lt;/div&gt;</p>\n" \
+            "<p>&lt;script&gt;alert(1)&lt;/script&gt;</p>\n" \
+            "<p>some &lt;b&gt;bold&lt;/b&gt; text</p>\n",
+          )
+        end
+      end
+
+      context 'when sanitized' do
+        let(:html) { 'sanitize' }
+
+        it 'keeps allowed tags and attributes only' do
+          expect(subject).to eq("<div>text</div>\n<p>some <b>bold</b> text</p>\n")
+        end
+
+        it 'drops urls of disallowed schemes hidden by character references' do
+          input = '<a href="javascript&#58;alert(1)">foo</a> <a href="&#106;avascript:alert(1)">bar</a>'
+
+          expect(described_class.convert(input, html: 'sanitize')).to eq("<p><a>foo</a> <a>bar</a></p>\n")
+        end
+      end
+    end
+
+    context 'with links of disallowed url schemes' do
+      let(:input) { '[foo](ftp://example.com) [bar](https://example.com)' }
+
+      it 'renders them as plain text by default' do
+        expect(described_class.convert(input)).to eq(
+          "<p>foo <a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">bar</a></p>\n",
+        )
+      end
+
+      it 'renders them with an empty href when configured' do
+        expect(described_class.convert(input, allowed_url_schemes: 'https', disallowed_link: 'empty_href')).to eq(
+          "<p><a href=\"\">foo</a> <a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">bar</a></p>\n",
+        )
+      end
+
+      it 'renders them as plain text without target and rel of links' do
+        expect(described_class.convert(input, internal_domain_name: false)).to eq(
+          "<p>foo <a href=\"https://example.com\">bar</a></p>\n",
+        )
+      end
+
+      it 'allows schemes added to the allowlist' do
+        expect(described_class.convert(input, allowed_url_schemes: 'ftp,https')).to include('<a href="ftp://example.com"')
+      end
+    end
+
+    context 'with external link options' do
+      let(:input) { '[foo](https://example.com)' }
+
+      it 'uses the configured rel' do
+        expect(described_class.convert(input, external_link_rel: 'noopener nofollow ugc')).to eq(
+          "<p><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener nofollow ugc\">foo</a></p>\n",
+        )
+      end
+
+      it 'does not open new tabs when disabled' do
+        expect(described_class.convert(input, external_link_target: false)).to eq(
+          "<p><a href=\"https://example.com\">foo</a></p>\n",
+        )
+      end
+
+      it 'tells that links open new windows when configured' do
+        expect(described_class.convert(input, external_link_hint: true, external_link_icon_class: 'icon-external')).to eq(
+          "<p><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer\">foo" \
+          "<span class=\"visually-hidden\">（新しいウィンドウで開きます）</span>" \
+          "<span class=\"icon-external\" aria-hidden=\"true\"></span></a></p>\n",
+        )
+      end
+    end
+
+    context 'with multiple internal domains' do
+      let(:input) { '[a](https://www.example.com) [b](https://example-tourism.jp) [c](https://forms.example.com)' }
+      let(:options) {
+        {
+          internal_domain_name:       ['example.com', '*-tourism.jp'],
+          internal_domain_exclusions: ['forms.example.com'],
+        }
+      }
+
+      it 'opens links to domains other than internal ones in new tabs' do
+        expect(described_class.convert(input, **options)).to eq(
+          "<p><a href=\"https://www.example.com\">a</a> <a href=\"https://example-tourism.jp\">b</a> " \
+          "<a href=\"https://forms.example.com\" target=\"_blank\" rel=\"noopener noreferrer\">c</a></p>\n",
+        )
+      end
+    end
+
+    context 'with scheme-less urls' do
+      let(:input) { '[foo](google.com/foo/bar) [bar](hoge.pdf)' }
+
+      it 'adds https:// to those starting with a hostname when configured' do
+        expect(described_class.convert(input, internal_domain_name: 'example.com', scheme_less_urls: 'https')).to eq(
+          "<p><a href=\"https://google.com/foo/bar\" target=\"_blank\" rel=\"noopener noreferrer\">foo</a> " \
+          "<a href=\"hoge.pdf\">bar</a></p>\n",
+        )
+      end
+
+      it 'keeps file names of top level domains relative unless they start with www.' do
+        input = '[a](setup.app) [b](report.pro) [c](www.example.app)'
+
+        expect(described_class.convert(input, internal_domain_name: 'example.com', scheme_less_urls: 'https')).to eq(
+          "<p><a href=\"setup.app\">a</a> <a href=\"report.pro\">b</a> " \
+          "<a href=\"https://www.example.app\" target=\"_blank\" rel=\"noopener noreferrer\">c</a></p>\n",
+        )
+      end
+    end
+
+    context 'with file link annotation' do
+      let(:input) { '[hoge](/foo/bar/hoge.pdf) [fuga](/foo/bar/fuga.xlsx) [top](/)' }
+
+      it 'adds file types and sizes given as a hash' do
+        expect(
+          described_class.convert(
+            input, internal_domain_name: 'example.com', file_link_annotation: true,
+                   file_sizes: { '/foo/bar/hoge.pdf' => 125_952 }
+          ),
+        ).to eq(
+          "<p><a href=\"/foo/bar/hoge.pdf\" class=\"file-link\">hoge（PDF：123KB）</a> " \
+          "<a href=\"/foo/bar/fuga.xlsx\" class=\"file-link\">fuga（XLSX）</a> <a href=\"/\">top</a></p>\n",
+        )
+      end
+
+      it 'looks up file sizes by the callback' do
+        paths = []
+        file_sizes = lambda do |path|
+          paths << path
+          2048 if path.end_with?('.xlsx')
+        end
+
+        expect(
+          described_class.convert(
+            input, internal_domain_name: 'example.com', file_link_annotation: true, file_sizes: file_sizes
+          ),
+        ).to eq(
+          "<p><a href=\"/foo/bar/hoge.pdf\" class=\"file-link\">hoge（PDF）</a> " \
+          "<a href=\"/foo/bar/fuga.xlsx\" class=\"file-link\">fuga（XLSX：2KB）</a> <a href=\"/\">top</a></p>\n",
+        )
+        expect(paths).to eq(['/foo/bar/hoge.pdf', '/foo/bar/fuga.xlsx'])
+      end
+
+      it 'adds them without internal_domain_name as well' do
+        expect(described_class.convert(input, preset: 'commonmark', file_link_annotation: true, file_sizes: ->(_path) { 2048 })).to eq(
+          "<p><a href=\"/foo/bar/hoge.pdf\" class=\"file-link\">hoge（PDF：2KB）</a> " \
+          "<a href=\"/foo/bar/fuga.xlsx\" class=\"file-link\">fuga（XLSX：2KB）</a> <a href=\"/\">top</a></p>\n",
+        )
+      end
+    end
+
+    context 'with base urls' do
+      let(:input) { '[foo](/foo/bar/1234) ![bar](images/bar.png) [baz](https://example.org)' }
+
+      it 'resolves relative links and images against the base urls' do
+        expect(
+          described_class.convert(
+            input, internal_domain_name: 'example.com', base_url: 'https://example.com/news/',
+                   asset_base_url: 'https://cdn.example.com/'
+          ),
+        ).to eq(
+          "<p><a href=\"https://example.com/foo/bar/1234\">foo</a> " \
+          "<img src=\"https://cdn.example.com/images/bar.png\" alt=\"bar\"> " \
+          "<a href=\"https://example.org\" target=\"_blank\" rel=\"noopener noreferrer\">baz</a></p>\n",
+        )
+      end
+
+      it 'raises an error for relative base urls' do
+        expect { described_class.convert(input, base_url: '/news/') }.to raise_error(
+          MarkdownIt::Error, 'invalid value for base_url: "/news/" is not an absolute url'
+        )
+      end
+    end
+
+    context 'with image attributes' do
+      let(:input) { '![画像タイトル](/images/1226-0009big.jpg)' }
+
+      it 'adds lazy loading, a class and srcset' do
+        expect(
+          described_class.convert(
+            input, image_lazy_loading: true, image_class: 'content-image',
+                   image_srcset_template: 'https://img.example.com/w{width}{path}', image_srcset_widths: [320, 640]
+          ),
+        ).to eq(
+          "<p><img class=\"content-image\" srcset=\"https://img.example.com/w320/images/1226-0009big.jpg 320w, " \
+          "https://img.example.com/w640/images/1226-0009big.jpg 640w\" loading=\"lazy\" decoding=\"async\" " \
+          "src=\"/images/1226-0009big.jpg\" alt=\"画像タイトル\"></p>\n",
+        )
+      end
+
+      it 'builds srcset from the url resolved against asset_base_url' do
+        expect(
+          described_class.convert(
+            input, asset_base_url: 'https://cdn.example.com/',
+                   image_srcset_template: 'https://resize.example.com/?w={width}&url={url}', image_srcset_widths: [320]
+          ),
+        ).to eq(
+          "<p><img srcset=\"https://resize.example.com/?w=320&amp;url=https%3A%2F%2Fcdn.example.com%2Fimages%2F1226-0009big.jpg 320w\" " \
+          "src=\"https://cdn.example.com/images/1226-0009big.jpg\" alt=\"画像タイトル\"></p>\n",
+        )
+      end
+
+      it 'looks up dimensions by the callback' do
+        image_dimensions = ->(url) { { width: 1200, height: 800 } if url.end_with?('big.jpg') }
+
+        expect(described_class.convert(input, image_dimensions: image_dimensions)).to eq(
+          "<p><img width=\"1200\" height=\"800\" src=\"/images/1226-0009big.jpg\" alt=\"画像タイトル\"></p>\n",
+        )
+      end
+    end
+
+    context 'with images without alt text' do
+      let(:input) { "![画像タイトル](/a.jpg)\n\n![](/b.jpg)" }
+
+      it 'fills them with the placeholder' do
+        expect(described_class.convert(input, missing_alt_text: 'placeholder', alt_text_placeholder: '写真')).to eq(
+          "<p><img src=\"/a.jpg\" alt=\"画像タイトル\"></p>\n<p><img src=\"/b.jpg\" alt=\"写真\"></p>\n",
+        )
+      end
+
+      it 'raises an error listing their lines' do
+        expect { described_class.convert(input, missing_alt_text: 'error') }.to raise_error(
+          MarkdownIt::Error, 'line 3: image without alt text (/b.jpg)'
+        )
+      end
+
+      it 'returns warnings along with the output' do
+        expect(described_class.convert_with_warnings(input, missing_alt_text: 'warning')).to eq(
+          [
+            "<p><img src=\"/a.jpg\" alt=\"画像タイトル\"></p>\n<p><img src=\"/b.jpg\" alt=\"\"></p>\n",
+            ['line 3: image without alt text (/b.jpg)'],
+          ],
+        )
+        expect(described_class.parse(input, missing_alt_text: 'warning').warnings).to eq(
+          ['line 3: image without alt text (/b.jpg)'],
+        )
+      end
+    end
+
+    context 'with image figures' do
+      let(:input) { "![市長](/a.jpg \"記者会見する市長\")\n\n![風景](/b.jpg)" }
+
+      it 'renders images with titles as captioned figures' do
+        expect(described_class.convert(input, image_figures: 'titled')).to eq(
+          "<figure><img src=\"/a.jpg\" alt=\"市長\" title=\"記者会見する市長\"><figcaption>記者会見する市長</figcaption></figure>\n" \
+          "<p><img src=\"/b.jpg\" alt=\"風景\"></p>\n",
+        )
+      end
+
+      it 'renders all the images standing alone as figures' do
+        expect(described_class.convert(input, image_figures: 'standalone', image_figure_class: 'photo')).to end_with(
+          "<figure class=\"photo\"><img src=\"/b.jpg\" alt=\"風景\"></figure>\n",
+        )
+      end
+    end
+
+    context 'with image proxy' do
+      let(:input) { '![a](https://example.com/a.jpg) ![b](https://www.city.example.jp/b.jpg)' }
+      let(:options) {
+        {
+          internal_domain_name: 'city.example.jp',
+          image_proxy_url:      'https://img.example.jp/proxy?url={url}&sig={hmac}',
+          image_proxy_key:      'secret',
+        }
+      }
+
+      it 'serves external images through the proxy with signatures' do
+        expect(described_class.convert(input, **options)).to eq(
+          "<p><img src=\"https://img.example.jp/proxy?url=https%3A%2F%2Fexample.com%2Fa.jpg&amp;" \
+          "sig=78c051af4dca895687bc48f1ea0de0fde3013cc14157ce78b0c81ba3ef2418f7\" alt=\"a\"> " \
+          "<img src=\"https://www.city.example.jp/b.jpg\" alt=\"b\"></p>\n",
+        )
+      end
+
+      it 'raises an error without the key to sign urls' do
+        expect { described_class.convert(input, **options, image_proxy_key: nil) }.to raise_error(
+          MarkdownIt::Error, 'image_proxy_key is required to sign image_proxy_url'
+        )
+      end
+    end
+
+    context 'with table decoration' do
+      let(:input) { "Table: 令和6年度の予算\n\n| 項目 | 金額 |\n|---|---|\n| 教育 | 100 |\n" }
+      let(:options) {
+        {
+          table_wrapper_class:     'table-responsive',
+          table_head_class:        'thead-light',
+          table_header_cell_class: 'header-cell',
+          table_cell_class:        'cell',
+          table_captions:          true,
+        }
+      }
+
+      it 'wraps tables, adds classes to their elements and captions them' do
+        expect(described_class.convert(input, **options)).to eq(
+          "<div class=\"table-responsive\">\n<table class=\"table\">\n<caption>令和6年度の予算</caption>\n" \
+          "<thead class=\"thead-light\">\n<tr>\n<th class=\"header-cell\">項目</th>\n<th class=\"header-cell\">金額</th>\n</tr>\n</thead>\n" \
+          "<tbody>\n<tr>\n<td class=\"cell\">教育</td>\n<td class=\"cell\">100</td>\n</tr>\n</tbody>\n</table>\n</div>\n",
+        )
+      end
+
+      it 'keeps the caption paragraph without table_captions' do
+        expect(described_class.convert(input, table_class_name: false)).to eq(
+          "<p>Table: 令和6年度の予算</p>\n<table>\n<thead>\n<tr>\n<th>項目</th>\n<th>金額</th>\n</tr>\n</thead>\n" \
+          "<tbody>\n<tr>\n<td>教育</td>\n<td>100</td>\n</tr>\n</tbody>\n</table>\n",
+        )
+      end
+    end
+
+    context 'with heading anchors' do
+      let(:input) { "## タイトル２-１\n\n## タイトル２-１" }
+
+      it 'adds unique ids to headings' do
+        expect(described_class.convert(input, heading_anchors: true)).to eq(
+          "<h2 id=\"タイトル２-１\">タイトル２-１</h2>\n<h2 id=\"タイトル２-１-1\">タイトル２-１</h2>\n",
+        )
+      end
+
+      it 'adds prefixed ids and self-links when configured' do
+        expect(
+          described_class.convert(input, heading_anchors: true, heading_anchor_prefix: 'h-', heading_anchor_link: true),
+        ).to start_with(
+          "<h2 id=\"h-タイトル２-１\"><a class=\"anchor\" href=\"#h-タイトル２-１\" aria-hidden=\"true\"></a>タイトル２-１</h2>\n",
+        )
+      end
+    end
+
+    context 'with table of contents' do
+      let(:input) { "[[toc]]\n\n# Section 1\n## Section 1-1" }
+
+      it 'renders the table of contents in place of the placeholder' do
+        expect(described_class.convert(input, table_of_contents: true, heading_level_offset: 1)).to eq(
+          "<nav class=\"table-of-contents\">\n<ul>\n<li><a href=\"#section-1\">Section 1</a>\n<ul>\n" \
+          "<li><a href=\"#section-1-1\">Section 1-1</a></li>\n</ul>\n</li>\n</ul>\n</nav>\n" \
+          "<h2 id=\"section-1\">Section 1</h2>\n<h3 id=\"section-1-1\">Section 1-1</h3>\n",
+        )
+      end
+    end
+
+    context 'with setext headings' do
+      let(:input) { "title 1\n=======\n\ntitle 2\n-------" }
+
+      it 'applies heading_level_offset as well' do
+        expect(described_class.convert(input, heading_level_offset: 2)).to eq("<h3>title 1</h3>\n<h4>title 2</h4>\n")
+      end
+    end
+
+    context 'with headings out of the allowed range' do
+      let(:input) { "# title 1\n\n###### title 6" }
+
+      it 'clamps them when configured' do
+        expect(described_class.convert(input, heading_level_offset: 1, heading_overflow: 'clamp')).to eq(
+          "<h2>title 1</h2>\n<h6>title 6</h6>\n",
+        )
+      end
+
+      it 'renders them as strong paragraphs when configured' do
+        expect(described_class.convert(input, heading_min_level: 2, heading_overflow: 'strong')).to eq(
+          "<p><strong>title 1</strong></p>\n<h6>title 6</h6>\n",
+        )
+      end
+
+      it 'raises an error listing the lines when configured' do
+        expect { described_class.convert(input, heading_level_offset: 1, heading_overflow: 'error') }.to raise_error(
+          MarkdownIt::Error, 'line 3: heading level 7 of "title 6" is out of the allowed range (1-6)'
+        )
+      end
+
+      it 'applies the range without the offset' do
+        expect { described_class.convert(input, preset: 'gfm', heading_max_level: 5, heading_overflow: 'error') }.to raise_error(
+          MarkdownIt::Error, 'line 3: heading level 6 of "title 6" is out of the allowed range (1-5)'
+        )
+        expect(described_class.convert(input, heading_level_offset: false, heading_min_level: 2, heading_overflow: 'clamp')).to eq(
+          "<h2>title 1</h2>\n<h6>title 6</h6>\n",
+        )
+      end
+    end
+
+    context 'with syntaxes turned off' do
+      let(:input) { "> quote\n\n| a |\n|---|\n| b |\n\n~~del~~ ![image](a.png) <b>html</b>" }
+
+      let(:options) {
+        {
+          blockquotes:   false,
+          tables:        false,
+          strikethrough: false,
+          typographer:   false,
+          images:        false,
+          html:          false,
+        }
+      }
+
+      it 'renders them as plain text' do
+        expect(described_class.convert(input, **options)).to eq(
+          "<p>&gt; quote</p>\n<p>| a |\n|---|\n| b |</p>\n" \
+          "<p>~~del~~ !<a href=\"a.png\" target=\"_blank\" rel=\"noopener noreferrer\">image</a> &lt;b&gt;html&lt;/b&gt;</p>\n",
+        )
+      end
+    end
+
+    context 'with presets' do
+      let(:input) { "# title\n\n| a |\n|---|\n| b |\n\n[link](https://example.com)" }
+
+      it 'renders plain CommonMark with the commonmark preset' do
+        expect(described_class.convert(input, preset: 'commonmark')).to eq(
+          "<h1>title</h1>\n<p>| a |\n|---|\n| b |</p>\n<p><a href=\"https://example.com\">link</a></p>\n",
+        )
+      end
+
+      it 'renders inline formatting and links only with the comment preset' do
+        expect(described_class.convert(input, preset: 'comment')).to eq(
+          "<p># title</p>\n<p>| a |\n|---|\n| b |</p>\n" \
+          "<p><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer nofollow ugc\">link</a></p>\n",
+        )
+      end
+
+      it 'lets options override the preset' do
+        expect(described_class.convert(input, preset: 'comment', headings: true)).to start_with("<h1>title</h1>\n")
+      end
+
+      it 'renders the same as the default with the cms preset' do
+        expect(described_class.convert(input, preset: 'cms')).to eq(described_class.convert(input))
+      end
+    end
+
+    context 'with invalid options' do
+      it 'raises an error suggesting the closest option for unknown ones' do
+        expect { described_class.convert(input, heading_levl_offset: 1) }.to raise_error(
+          MarkdownIt::Error, 'unknown option: heading_levl_offset (did you mean heading_level_offset?)'
+        )
+      end
+
+      it 'raises an error for values of wrong types' do
+        expect { described_class.convert(input, heading_level_offset: 'two') }.to raise_error(
+          MarkdownIt::Error, 'invalid value for heading_level_offset: expected an integer between 0 and 6 or false, got "two"'
+        )
+      end
+
+      it 'accepts numbers and booleans given as strings (e.g. from YAML or ENV)' do
+        expect(described_class.convert(input, heading_level_offset: '1', table_class_name: 'false')).to eq(
+          described_class.convert(input, heading_level_offset: 1, table_class_name: false),
+        )
+      end
+
+      it 'raises an error for values out of range' do
+        expect { described_class.convert(input, heading_level_offset: 7) }.to raise_error(
+          MarkdownIt::Error, 'invalid value for heading_level_offset: expected an integer between 0 and 6 or false, got 7'
+        )
+      end
+
+      it 'ignores options given as nil' do
+        expect(described_class.convert(input, heading_level_offset: nil)).to eq("<h1>title 1</h1>\n<p>some random markdown</p>\n")
+      end
+    end
+  end
+
+  describe 'MarkdownIt.convert_many' do
+    let(:inputs) { Array.new(20) { |i| "# title #{i}\n\nsome random markdown" } }
+
+    it 'converts all documents in order' do
+      expect(described_class.convert_many(inputs, heading_level_offset: 1)).to eq(
+        inputs.map { |input| described_class.convert(input, heading_level_offset: 1) },
+      )
+    end
+
+    it 'raises an error listing the failed documents' do
+      expect { described_class.convert_many(['# title', '###### title 6'], heading_level_offset: 1, heading_overflow: 'error') }.to raise_error(
+        MarkdownIt::Error, 'document at index 1: line 1: heading level 7 of "title 6" is out of the allowed range (1-6)'
+      )
+    end
+  end
+
+  describe 'MarkdownIt::Parser' do
+    subject(:parser) { MarkdownIt::Parser.new(**options) }
+
+    let(:options) { { heading_level_offset: 2, table_class_name: 'table-class' } }
+
+    it 'converts markdown to html' do
+      expect(parser.convert("# title 1\nsome random markdown")).to eq("<h3>title 1</h3>\n<p>some random markdown</p>\n")
+    end
+
+    it 'can be reused for multiple documents' do
+      outputs = ['# title 1', '## title 2'].map { |input| parser.convert(input) }
+      expect(outputs).to eq(["<h3>title 1</h3>\n", "<h4>title 2</h4>\n"])
+    end
+
+    it 'returns correct results when shared among threads' do
+      threads = Array.new(4) { |i| Thread.new { parser.convert("# title #{i}") } }
+      expect(threads.map(&:value)).to eq(Array.new(4) { |i| "<h3>title #{i}</h3>\n" })
+    end
+
+    it 'lets other Ruby threads run while converting a large document' do
+      input = "# title\n\nsome *random* [markdown](https://example.com)\n\n" * 200_000
+      clock = -> { Process.clock_gettime(Process::CLOCK_MONOTONIC) }
+      ticks = []
+      ticker = Thread.new do
+        loop do
+          ticks << clock.call
+          sleep 0.001
+        end
+      end
+      sleep 0.01 while ticks.empty?
+
+      started = clock.call
+      parser.convert(input)
+      finished = clock.call
+      ticker.kill
+
+      # the ticker would stall for the whole conversion if the GVL were held throughout,
+      # whereas it wakes up every millisecond or so otherwise, however long the conversion takes
+      stamps = [started, *ticks.select { |tick| tick > started && tick < finished }, finished]
+      longest_gap = stamps.each_cons(2).map { |before, after| after - before }.max
+      expect(longest_gap).to be < (finished - started) / 2
+    end
+
+    it 'produces the same output as MarkdownIt.convert' do
+      input = File.read('spec/fixtures/dummy_input.md')
+      expect(parser.convert(input)).to eq(MarkdownIt.convert(input, **options))
+    end
+
+    it 'takes lookups as hashes' do
+      parser = MarkdownIt::Parser.new(
+        internal_domain_name: 'example.com', file_link_annotation: true, file_sizes: { '/files/hoge.pdf' => 125_952 },
+      )
+      expect(parser.convert('[hoge](/files/hoge.pdf)')).to include('hoge（PDF：123KB）')
+    end
+
+    it 'raises an error for lookup callbacks' do
+      expect { MarkdownIt::Parser.new(file_link_annotation: true, file_sizes: ->(_path) { 125_952 }) }.to raise_error(
+        MarkdownIt::Error, 'invalid value for file_sizes: callbacks are not supported by MarkdownIt::Parser, give a hash instead'
+      )
+    end
+  end
+
+  describe 'MarkdownIt.parse' do
+    subject(:document) { described_class.parse(input, **options) }
+
+    let(:input) { "## title 1\nsome [random](https://example.com) markdown" }
+    let(:options) { { heading_level_offset: 1 } }
+
+    it 'returns a document' do
+      expect(document).to be_a(MarkdownIt::Document)
+    end
+
+    it 'exposes the root node' do
+      expect(document.root.type).to eq('Root')
+    end
+
+    it 'walks through all the nodes' do
+      expect(document.map(&:type)).to eq(%w[Root ATXHeading Text Paragraph Text LinkWithTarget Text Text])
+    end
+
+    context 'with a heading node' do
+      subject(:heading) { document.children.first }
+
+      it 'exposes the type, attributes, text and source position' do
+        expect(heading).to have_attributes(
+          type:            'ATXHeading',
+          attributes:      { 'level' => '3' },
+          text:            'title 1',
+          source_position: [1, 1, 1, 10],
+        )
+      end
+    end
+
+    context 'with table of contents' do
+      let(:input) { "# Section 1\n## Section 1-1\n# Section 2" }
+      let(:options) { { table_of_contents: true } }
+
+      it 'returns the nested headings' do
+        expect(document.toc).to eq(
+          [
+            {
+              'level'    => 1,
+              'id'       => 'section-1',
+              'text'     => 'Section 1',
+              'children' => [{ 'level' => 2, 'id' => 'section-1-1', 'text' => 'Section 1-1', 'children' => [] }],
+            },
+            { 'level' => 1, 'id' => 'section-2', 'text' => 'Section 2', 'children' => [] },
+          ],
+        )
+      end
+    end
+
+    context 'without table of contents' do
+      it 'returns an empty list' do
+        expect(document.toc).to eq([])
+      end
+    end
+
+    context 'with a link node' do
+      subject(:link) { document.find { |node| node.type == 'LinkWithTarget' } }
+
+      it 'exposes the link attributes' do
+        expect(link.attributes).to eq(
+          'href'   => 'https://example.com',
+          'target' => '_blank',
+          'rel'    => 'noopener noreferrer',
+        )
+      end
+    end
+  end
+
+  describe 'MarkdownIt.to_mdast' do
+    subject(:mdast) { JSON.parse(described_class.to_mdast(input, **options)) }
+
+    let(:input) { "# title 1\nsome [random](https://example.com) markdown" }
+    let(:options) { { heading_level_offset: 1 } }
+
+    it 'returns the document as mdast' do
+      expect(mdast).to include(
+        'type'     => 'root',
+        'children' => [
+          include('type' => 'heading', 'depth' => 2, 'children' => [include('type' => 'text', 'value' => 'title 1')]),
+          include(
+            'type'     => 'paragraph',
+            'children' => [
+              include('type' => 'text', 'value' => 'some '),
+              include(
+                'type' => 'link',
+                'url'  => 'https://example.com',
+                'data' => { 'hProperties' => { 'target' => '_blank', 'rel' => 'noopener noreferrer' } },
+              ),
+              include('type' => 'text', 'value' => ' markdown'),
+            ],
+          ),
+        ],
+      )
+    end
+
+    it 'includes source positions' do
+      expect(mdast['children'].first['position']).to eq(
+        'start' => { 'line' => 1, 'column' => 1 },
+        'end'   => { 'line' => 1, 'column' => 10 },
+      )
+    end
   end
 end
@@ -1,5 +1,6 @@
 # frozen_string_literal: true
 
+require "json"
 require "markdown_it_ruby"
 
 RSpec.configure do |config|